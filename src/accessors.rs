@@ -0,0 +1,93 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Creates null-checked getter functions for the fields of a type exposed to C.
+///
+/// `define_getters!(MyType { id: u32, name: String })` generates
+/// `my_type_get_id(*const MyType) -> u32` and
+/// `my_type_get_name(*const MyType) -> *mut c_char`.
+///
+/// Non-string fields must be `Copy` and are returned by value; a null pointer
+/// yields `Default::default()`. `String` fields are returned as a freshly
+/// allocated C string which the caller must free with `destroy_c_char`;
+/// a null pointer yields a null string. In debug builds, a pointer that is not
+/// aligned for the type aborts instead of being read.
+#[macro_export]
+macro_rules! define_getters (
+    ($t:ident { $($field:ident : $ret:ident),+ $(,)? }) => (
+        $( $crate::define_getters!(@getter $t, $field, $ret); )+
+    );
+    (@getter $t:ident, $field:ident, String) => (
+        $crate::__paste! {
+            #[unsafe(no_mangle)]
+            pub extern "C" fn [<$t:snake _get_ $field>](obj: *const $t) -> *mut ::core::ffi::c_char {
+                $crate::assert_aligned!(obj, $t);
+                match unsafe { obj.as_ref() } {
+                    Some(obj) => $crate::string::string_to_c_char(obj.$field.as_str()),
+                    None => ::core::ptr::null_mut(),
+                }
+            }
+        }
+    );
+    (@getter $t:ident, $field:ident, $ret:ty) => (
+        $crate::__paste! {
+            #[unsafe(no_mangle)]
+            pub extern "C" fn [<$t:snake _get_ $field>](obj: *const $t) -> $ret {
+                $crate::assert_aligned!(obj, $t);
+                match unsafe { obj.as_ref() } {
+                    Some(obj) => obj.$field,
+                    None => <$ret as ::core::default::Default>::default(),
+                }
+            }
+        }
+    );
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::string::c_char_to_string;
+    use std::ffi::CString;
+
+    pub struct SampleRecord {
+        id: i32,
+        name: String,
+    }
+
+    define_getters!(SampleRecord {
+        id: i32,
+        name: String
+    });
+
+    #[test]
+    fn test_generated_int_getter() {
+        let record = SampleRecord {
+            id: 42,
+            name: String::from("Sample"),
+        };
+
+        assert_eq!(sample_record_get_id(&record), 42);
+    }
+
+    #[test]
+    fn test_generated_string_getter() {
+        let record = Box::into_raw(Box::new(SampleRecord {
+            id: 1,
+            name: String::from("Hello 世界"),
+        }));
+
+        let name = sample_record_get_name(record);
+        assert_eq!(c_char_to_string(name), "Hello 世界");
+
+        unsafe {
+            let _ = CString::from_raw(name);
+            let _ = Box::from_raw(record);
+        }
+    }
+
+    #[test]
+    fn test_generated_getters_null_checked() {
+        assert_eq!(sample_record_get_id(std::ptr::null()), 0);
+        assert!(sample_record_get_name(std::ptr::null()).is_null());
+    }
+}
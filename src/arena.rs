@@ -0,0 +1,193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+
+use crate::result::{ErrorCode, ExternResult, OwnedExternResult, extern_result_get_ok};
+
+/// Owns every `ExternResult` it hands out, together with its `ok` value and
+/// error, and frees them all at once on `clear` or drop.
+///
+/// This suits functions that return many small results in a loop: C keeps one
+/// arena handle and releases everything with a single `result_arena_clear`
+/// instead of freeing each result.
+///
+/// #Safety
+///
+/// Results from an arena stay valid until the arena is cleared or destroyed and
+/// must never be freed individually, e.g. with `extern_result_destroy`.
+#[derive(Debug, Default)]
+pub struct ResultArena {
+    results: Vec<(*mut ExternResult, Option<DropOk>)>,
+}
+
+/// Frees a type-erased `ok` value with the type it was created with.
+type DropOk = unsafe fn(*const c_void);
+
+/// Releases an `ok` value that was boxed as a `T`.
+unsafe fn drop_boxed<T>(ok: *const c_void) {
+    let _ = unsafe { Box::from_raw(crate::result::untagged_ok(ok) as *mut T) };
+}
+
+impl ResultArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a success result holding `result`, owned by the arena.
+    pub fn ok<T>(&mut self, result: T) -> *mut ExternResult {
+        let ptr = ExternResult::ok(result);
+        self.results.push((ptr, Some(drop_boxed::<T>)));
+        ptr
+    }
+
+    /// Creates a success result without a value, owned by the arena.
+    pub fn ok_null(&mut self) -> *mut ExternResult {
+        let ptr = ExternResult::ok_null();
+        self.results.push((ptr, None));
+        ptr
+    }
+
+    /// Creates an error result, owned by the arena.
+    pub fn err<S>(&mut self, code: ErrorCode, msg: S) -> *mut ExternResult
+    where
+        S: Into<String>,
+    {
+        let ptr = ExternResult::err(code, msg);
+        self.results.push((ptr, None));
+        ptr
+    }
+
+    /// The number of results currently owned by the arena.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether the arena owns no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Frees every result handed out so far. The arena can be reused afterwards.
+    pub fn clear(&mut self) {
+        for (ptr, drop_ok) in self.results.drain(..) {
+            let ok = extern_result_get_ok(ptr);
+            if let Some(drop_ok) = drop_ok
+                && !ok.is_null()
+            {
+                unsafe { drop_ok(ok) };
+            }
+            drop(unsafe { OwnedExternResult::from_raw(ptr) });
+        }
+    }
+}
+
+impl Drop for ResultArena {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Creates an empty arena. Release it with `result_arena_destroy`.
+#[unsafe(export_name = ffi_symbol!("result_arena_new"))]
+pub extern "C" fn result_arena_new() -> *mut ResultArena {
+    Box::into_raw(Box::new(ResultArena::new()))
+}
+
+/// Frees every result owned by `arena`, keeping the arena itself for reuse.
+/// A null `arena` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("result_arena_clear"))]
+pub extern "C" fn result_arena_clear(arena: *mut ResultArena) {
+    if let Some(arena) = unsafe { arena.as_mut() } {
+        arena.clear();
+    }
+}
+
+/// Frees `arena` and every result it owns. A null `arena` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("result_arena_destroy"))]
+pub extern "C" fn result_arena_destroy(arena: *mut ResultArena) {
+    if !arena.is_null() {
+        let _ = unsafe { Box::from_raw(arena) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{extern_result_get_err, extern_result_is_ok};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_arena_clear_frees_every_result() {
+        let drops = Rc::new(Cell::new(0));
+        let arena = result_arena_new();
+
+        for i in 0..1000 {
+            let result = match i % 3 {
+                0 => unsafe { &mut *arena }.ok(DropCounter(drops.clone())),
+                1 => unsafe { &mut *arena }.ok_null(),
+                _ => unsafe { &mut *arena }.err(ErrorCode::Other, format!("Failure {}", i)),
+            };
+            assert_eq!(extern_result_is_ok(result), i % 3 != 2);
+        }
+        assert_eq!(unsafe { &*arena }.len(), 1000);
+
+        result_arena_clear(arena);
+        assert_eq!(drops.get(), 334);
+        assert!(unsafe { &*arena }.is_empty());
+
+        result_arena_destroy(arena);
+        assert_eq!(drops.get(), 334);
+    }
+
+    #[test]
+    fn test_arena_reuse_after_clear() {
+        let mut arena = ResultArena::new();
+
+        let first = arena.ok(String::from("first"));
+        assert_eq!(
+            unsafe { &*(extern_result_get_ok(first) as *const String) },
+            "first"
+        );
+        arena.clear();
+
+        let second = arena.err(ErrorCode::NotFoundError, "Missing");
+        assert_eq!(
+            unsafe { &*extern_result_get_err(second) }.code(),
+            ErrorCode::NotFoundError
+        );
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn test_arena_drop_frees_results() {
+        let drops = Rc::new(Cell::new(0));
+        {
+            let mut arena = ResultArena::new();
+            arena.ok(DropCounter(drops.clone()));
+            arena.ok(DropCounter(drops.clone()));
+        }
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn test_arena_null_handle() {
+        result_arena_clear(std::ptr::null_mut());
+        result_arena_destroy(std::ptr::null_mut());
+    }
+}
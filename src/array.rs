@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Creates a `#[repr(C)]` type `$name` wrapping a `[u8; $len]` that is returned
+/// by value, e.g. for hashes, instead of being boxed.
+///
+/// Also creates `<name>_copy(arr, out, out_len)` (the snake-cased type name,
+/// e.g. `extern_array32_copy`), which copies the bytes into a C buffer. It returns
+/// false without writing anything if either pointer is null or `out_len` is
+/// smaller than `$len`.
+#[macro_export]
+macro_rules! define_fixed_array (
+    ($(#[$meta:meta])* $name:ident, $len:literal) => (
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name {
+            pub bytes: [u8; $len],
+        }
+
+        impl $name {
+            /// The number of bytes in the array.
+            pub const LEN: usize = $len;
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                $name { bytes }
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(array: $name) -> Self {
+                array.bytes
+            }
+        }
+
+        $crate::__paste! {
+            #[allow(clippy::not_unsafe_ptr_arg_deref)]
+            #[unsafe(no_mangle)]
+            pub extern "C" fn [<$name:snake _copy>](
+                arr: *const $name,
+                out: *mut u8,
+                out_len: usize,
+            ) -> bool {
+                match unsafe { arr.as_ref() } {
+                    Some(arr) if !out.is_null() && out_len >= $len => {
+                        unsafe { ::core::ptr::copy_nonoverlapping(arr.bytes.as_ptr(), out, $len) };
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }
+    )
+);
+
+define_fixed_array!(
+    /// A 16-byte array passed by value, e.g. a raw UUID.
+    ExternArray16,
+    16
+);
+define_fixed_array!(
+    /// A 32-byte array passed by value, e.g. a SHA-256 hash.
+    ExternArray32,
+    32
+);
+define_fixed_array!(
+    /// A 64-byte array passed by value, e.g. a SHA-512 hash or a signature.
+    ExternArray64,
+    64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_hash() -> [u8; 32] {
+        core::array::from_fn(|i| i as u8 * 7)
+    }
+
+    #[test]
+    fn test_array32_round_trip() {
+        let array = ExternArray32::from(sample_hash());
+
+        assert_eq!(ExternArray32::LEN, 32);
+        assert_eq!(array.bytes, sample_hash());
+        assert_eq!(<[u8; 32]>::from(array), sample_hash());
+    }
+
+    #[test]
+    fn test_array32_copy() {
+        let array = ExternArray32::from(sample_hash());
+        let mut out = [0u8; 40];
+
+        assert!(extern_array32_copy(&array, out.as_mut_ptr(), out.len()));
+        assert_eq!(out[..32], sample_hash());
+        assert_eq!(out[32..], [0; 8]);
+    }
+
+    #[test]
+    fn test_array32_copy_rejects_short_or_null_buffers() {
+        let array = ExternArray32::from(sample_hash());
+        let mut out = [0u8; 31];
+
+        assert!(!extern_array32_copy(&array, out.as_mut_ptr(), out.len()));
+        assert_eq!(out, [0; 31]);
+        assert!(!extern_array32_copy(&array, std::ptr::null_mut(), 32));
+        assert!(!extern_array32_copy(std::ptr::null(), out.as_mut_ptr(), 32));
+    }
+
+    #[test]
+    fn test_array_layout() {
+        assert_eq!(std::mem::size_of::<ExternArray16>(), 16);
+        assert_eq!(std::mem::size_of::<ExternArray64>(), 64);
+        assert_eq!(std::mem::align_of::<ExternArray64>(), 1);
+    }
+}
@@ -0,0 +1,404 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::slice::ExternSlice;
+
+/// A C representation of an owned byte buffer.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// A destructor `extern_buffer_destroy` is provided for releasing the memory for this type.
+pub type ExternBuffer = ExternSlice<u8>;
+
+/// Releases an `ExternBuffer` created by this crate.
+#[unsafe(export_name = ffi_symbol!("extern_buffer_destroy"))]
+pub extern "C" fn extern_buffer_destroy(buf: ExternBuffer) {
+    if !buf.data.is_null() {
+        let _ = unsafe { Vec::from_raw_parts(buf.data, buf.len, buf.len) };
+    }
+}
+
+/// Copies the contents of `buf` into a new `Vec`, leaving ownership of the
+/// buffer with the caller. Returns an empty `Vec` for a null or empty buffer,
+/// or one whose `len` is too large to be a valid allocation.
+///
+/// # Safety
+///
+/// `buf` must be null or point to a valid `ExternBuffer` whose `data` is
+/// readable for `len` bytes.
+pub unsafe fn extern_buffer_to_vec(buf: *const ExternBuffer) -> Vec<u8> {
+    match unsafe { buf.as_ref() } {
+        Some(buf)
+            if !buf.data.is_null()
+                && buf.len > 0
+                && crate::memory::checked_layout::<u8>(buf.len).is_some() =>
+        {
+            unsafe { core::slice::from_raw_parts(buf.data, buf.len) }.to_vec()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Lends `data` to `f` as a C pointer and length, e.g. to feed a read-only buffer
+/// to a C function, without copying it or transferring ownership. An empty slice
+/// is passed as a null pointer with a length of 0.
+///
+/// The pointer is only valid during the call, so C must neither free it nor keep
+/// it after `f` returns.
+pub fn with_byte_slice<R>(data: &[u8], f: impl FnOnce(*const u8, usize) -> R) -> R {
+    if data.is_empty() {
+        return f(core::ptr::null(), 0);
+    }
+    f(data.as_ptr(), data.len())
+}
+
+/// A C representation of a list of flags packed 8 per byte, least significant
+/// bit first, so flag `i` is bit `i % 8` of byte `i / 8`. `bit_len` is the number
+/// of flags; the unused high bits of the last byte are zero.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// A destructor `extern_bitset_destroy` is provided for releasing the memory for this type.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExternBitset {
+    pub bytes: ExternBuffer,
+    pub bit_len: usize,
+}
+
+/// Packs `flags` into an `ExternBitset`, e.g. to hand C a large flag array in an
+/// eighth of the space of one byte per `bool`.
+pub fn vec_bool_to_bitset(flags: &[bool]) -> ExternBitset {
+    let mut bytes = alloc::vec![0u8; flags.len().div_ceil(8)];
+    for (i, &flag) in flags.iter().enumerate() {
+        if flag {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    ExternBitset {
+        bytes: ExternSlice::from_vec(bytes),
+        bit_len: flags.len(),
+    }
+}
+
+/// Unpacks `bit_len` flags from bytes laid out like `ExternBitset`. A null `ptr`
+/// yields an empty `Vec`.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to at least `bit_len.div_ceil(8)` readable bytes.
+pub unsafe fn bitset_to_vec_bool(ptr: *const u8, bit_len: usize) -> Vec<bool> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(ptr, bit_len.div_ceil(8)) };
+    (0..bit_len)
+        .map(|i| bytes[i / 8] & (1 << (i % 8)) != 0)
+        .collect()
+}
+
+/// Releases an `ExternBitset` created by this crate.
+#[unsafe(export_name = ffi_symbol!("extern_bitset_destroy"))]
+pub extern "C" fn extern_bitset_destroy(bitset: ExternBitset) {
+    extern_buffer_destroy(bitset.bytes);
+}
+
+/// A C representation of a list of byte buffers, e.g. a batch of encoded
+/// records, where each of the `len` entries of `items` owns its own bytes.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// A destructor `extern_buffer_array_destroy` is provided for releasing the memory
+/// for this pointer type, including every inner buffer.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExternBufferArray {
+    pub items: *mut ExternBuffer,
+    pub len: usize,
+}
+
+impl ExternBufferArray {
+    /// Builds an array from byte buffers, keeping the order in which `buffers`
+    /// yields them. An empty array has a null `items` pointer.
+    pub fn new<I>(buffers: I) -> *mut Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let items: Vec<ExternBuffer> = buffers.into_iter().map(ExternSlice::from_vec).collect();
+        let items = ExternSlice::from_vec(items);
+        Box::into_raw(Box::new(ExternBufferArray {
+            items: items.data,
+            len: items.len,
+        }))
+    }
+}
+
+/// Releases an `ExternBufferArray`, every inner buffer and then the array itself.
+/// A null `arr` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_buffer_array_destroy"))]
+pub extern "C" fn extern_buffer_array_destroy(arr: *mut ExternBufferArray) {
+    if arr.is_null() {
+        return;
+    }
+    let arr = unsafe { Box::from_raw(arr) };
+    if !arr.items.is_null() {
+        let items = unsafe { Vec::from_raw_parts(arr.items, arr.len, arr.len) };
+        for buf in items {
+            extern_buffer_destroy(buf);
+        }
+    }
+}
+
+/// Returns the buffer at `index`, or null if `arr` is null or `index` is out of
+/// bounds. The buffer is owned by `arr` and must not be freed.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("buffer_array_get"))]
+pub extern "C" fn buffer_array_get(
+    arr: *const ExternBufferArray,
+    index: usize,
+) -> *const ExternBuffer {
+    match unsafe { arr.as_ref() } {
+        Some(arr) if index < arr.len => unsafe { arr.items.add(index) },
+        _ => core::ptr::null(),
+    }
+}
+
+/// A `std::io::Write` sink that accumulates bytes produced incrementally and
+/// hands them to C as a single `ExternBuffer` once finished.
+#[derive(Debug, Default)]
+pub struct CBufWriter {
+    buf: Vec<u8>,
+}
+
+impl CBufWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        CBufWriter {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Finishes writing and transfers the accumulated bytes to an `ExternBuffer`.
+    pub fn into_extern_buffer(self) -> ExternBuffer {
+        ExternSlice::from_vec(self.buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for CBufWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.buf.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use std::io::Write;
+
+    fn buffer_contents(buf: &ExternBuffer) -> &[u8] {
+        if buf.data.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(buf.data, buf.len) }
+        }
+    }
+
+    #[test]
+    fn test_with_byte_slice_lends_without_copying() {
+        let data = b"read-only \x00\xff bytes".to_vec();
+
+        let (ptr, copy) = with_byte_slice(&data, |ptr, len| {
+            let bytes = unsafe { core::slice::from_raw_parts(ptr, len) };
+            (ptr, bytes.to_vec())
+        });
+
+        assert_eq!(copy, data);
+        // C saw the original bytes, not a copy
+        assert_eq!(ptr, data.as_ptr());
+    }
+
+    #[test]
+    fn test_bitset_round_trip() {
+        let flags = [
+            true, false, true, true, false, false, false, true, // 0b1000_1101
+            false, true, true, false, true, // 0b0001_0110
+        ];
+
+        let bitset = vec_bool_to_bitset(&flags);
+
+        assert_eq!(bitset.bit_len, 13);
+        assert_eq!(buffer_contents(&bitset.bytes), [0b1000_1101, 0b0001_0110]);
+        let unpacked = unsafe { bitset_to_vec_bool(bitset.bytes.data, bitset.bit_len) };
+        assert_eq!(unpacked, flags);
+
+        extern_bitset_destroy(bitset);
+    }
+
+    #[test]
+    fn test_bitset_empty_and_null() {
+        let bitset = vec_bool_to_bitset(&[]);
+        assert_eq!(bitset.bit_len, 0);
+        assert!(bitset.bytes.data.is_null());
+        assert!(unsafe { bitset_to_vec_bool(bitset.bytes.data, bitset.bit_len) }.is_empty());
+        extern_bitset_destroy(bitset);
+
+        assert!(unsafe { bitset_to_vec_bool(std::ptr::null(), 13) }.is_empty());
+    }
+
+    #[test]
+    fn test_with_byte_slice_empty() {
+        let (ptr, len) = with_byte_slice(&[], |ptr, len| (ptr, len));
+
+        assert!(ptr.is_null());
+        assert_eq!(len, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_writer_multiple_chunks() {
+        let mut writer = CBufWriter::new();
+        writer.write_all(b"Hello").unwrap();
+        writer.write_all(b", ").unwrap();
+        let name = "World";
+        write!(writer, "{} {}", name, 42).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(writer.len(), 15);
+        let buf = writer.into_extern_buffer();
+
+        assert_eq!(buf.len, 15);
+        assert_eq!(buffer_contents(&buf), b"Hello, World 42");
+
+        extern_buffer_destroy(buf);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_writer_binary_data() {
+        let mut writer = CBufWriter::with_capacity(4);
+        for chunk in [[0u8, 1], [254, 255], [0, 0]] {
+            writer.write_all(&chunk).unwrap();
+        }
+        let buf = writer.into_extern_buffer();
+
+        assert_eq!(buffer_contents(&buf), &[0, 1, 254, 255, 0, 0]);
+
+        extern_buffer_destroy(buf);
+    }
+
+    #[test]
+    fn test_extern_buffer_to_vec_copies() {
+        let buf = ExternSlice::from_vec(vec![1u8, 2, 3, 4]);
+
+        let mut copy = unsafe { extern_buffer_to_vec(&buf) };
+        assert_eq!(copy, [1, 2, 3, 4]);
+
+        // The copy is independent of the buffer, which is still owned by the caller
+        copy[0] = 100;
+        copy.push(5);
+        assert_eq!(buffer_contents(&buf), &[1, 2, 3, 4]);
+
+        extern_buffer_destroy(buf);
+    }
+
+    #[test]
+    fn test_extern_buffer_to_vec_null_and_empty() {
+        let empty = ExternSlice::from_vec(Vec::new());
+
+        assert!(unsafe { extern_buffer_to_vec(std::ptr::null()) }.is_empty());
+        assert!(unsafe { extern_buffer_to_vec(&empty) }.is_empty());
+
+        extern_buffer_destroy(empty);
+    }
+
+    #[test]
+    fn test_extern_buffer_to_vec_absurd_length() {
+        let bogus = ExternSlice {
+            data: std::ptr::NonNull::<u8>::dangling().as_ptr(),
+            len: usize::MAX,
+        };
+
+        assert!(unsafe { extern_buffer_to_vec(&bogus) }.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_array_round_trip() {
+        let blobs = vec![vec![1u8], Vec::new(), vec![0xff; 300]];
+        let arr = ExternBufferArray::new(blobs.clone());
+
+        assert_eq!(unsafe { &*arr }.len, 3);
+        for (i, blob) in blobs.iter().enumerate() {
+            let buf = buffer_array_get(arr, i);
+            assert_eq!(unsafe { extern_buffer_to_vec(buf) }, *blob);
+        }
+
+        extern_buffer_array_destroy(arr);
+    }
+
+    #[test]
+    fn test_buffer_array_get_out_of_bounds() {
+        let arr = ExternBufferArray::new([b"only".to_vec()]);
+
+        assert!(!buffer_array_get(arr, 0).is_null());
+        assert!(buffer_array_get(arr, 1).is_null());
+        assert!(buffer_array_get(std::ptr::null(), 0).is_null());
+
+        extern_buffer_array_destroy(arr);
+    }
+
+    #[test]
+    fn test_buffer_array_empty() {
+        let arr = ExternBufferArray::new(Vec::new());
+
+        assert!(unsafe { &*arr }.items.is_null());
+        assert!(buffer_array_get(arr, 0).is_null());
+
+        extern_buffer_array_destroy(arr);
+        extern_buffer_array_destroy(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_writer_empty() {
+        let writer = CBufWriter::new();
+        assert!(writer.is_empty());
+
+        let buf = writer.into_extern_buffer();
+        assert_eq!(buf.len, 0);
+
+        extern_buffer_destroy(buf);
+    }
+}
@@ -0,0 +1,124 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Creates a `#[repr(C)]` enum whose discriminants are spelled out, so reordering
+/// the variants never changes the values C sees, e.g.
+/// `define_c_enum!(pub enum Color { Red = 1, Green = 2 });`.
+///
+/// Alongside the enum this creates:
+/// - `TryFrom<i32>`, which rejects unknown values by returning them as the error,
+///   and `From<Name> for i32`; `value as i32` works as for any fieldless enum
+/// - `VARIANTS`, every variant in declaration order
+/// - `name()`, the variant name as a static C string
+/// - `<name>_name(value)` (the snake-cased type name, e.g. `color_name`), which
+///   returns that name to C; the string is static and must not be freed
+///
+/// The names are NUL-terminated literals built with `concat!` at compile time,
+/// so every call for a variant returns the same pointer and never allocates.
+#[macro_export]
+macro_rules! define_c_enum (
+    (
+        @name_fn [$($name_fn_attr:tt)*]
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal),* $(,)?
+        }
+    ) => (
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant = $value),*
+        }
+
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const VARIANTS: &'static [$name] = &[$($name::$variant),*];
+
+            /// The name of the variant as a static C string.
+            pub const fn name(self) -> &'static ::core::ffi::CStr {
+                match self {
+                    $($name::$variant => unsafe {
+                        // An identifier never contains a NUL
+                        ::core::ffi::CStr::from_bytes_with_nul_unchecked(
+                            concat!(stringify!($variant), "\0").as_bytes(),
+                        )
+                    }),*
+                }
+            }
+        }
+
+        impl ::core::convert::TryFrom<i32> for $name {
+            type Error = i32;
+
+            fn try_from(value: i32) -> Result<Self, i32> {
+                match value {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(value),
+                }
+            }
+        }
+
+        impl From<$name> for i32 {
+            fn from(value: $name) -> Self {
+                value as i32
+            }
+        }
+
+        $crate::__paste! {
+            #[doc = "Returns the name of `value` as a static C string, which must not be freed."]
+            #[$($name_fn_attr)*]
+            pub extern "C" fn [<$name:snake _name>](value: $name) -> *const ::core::ffi::c_char {
+                value.name().as_ptr()
+            }
+        }
+    );
+    ($($enum:tt)*) => (
+        $crate::define_c_enum!(@name_fn [unsafe(no_mangle)] $($enum)*);
+    );
+);
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    define_c_enum!(
+        /// A sample enum with gaps and a negative value.
+        pub enum Color {
+            Red = 1,
+            Green = 2,
+            /// Deliberately out of order
+            Blue = 8,
+            Unknown = -1,
+        }
+    );
+
+    #[test]
+    fn test_c_enum_round_trip() {
+        for &color in Color::VARIANTS {
+            assert_eq!(Color::try_from(color as i32), Ok(color));
+            assert_eq!(Color::try_from(i32::from(color)), Ok(color));
+        }
+        assert_eq!(Color::Blue as i32, 8);
+        assert_eq!(Color::Unknown as i32, -1);
+    }
+
+    #[test]
+    fn test_c_enum_rejects_unknown_values() {
+        assert_eq!(Color::try_from(0), Err(0));
+        assert_eq!(Color::try_from(3), Err(3));
+        assert_eq!(Color::try_from(i32::MIN), Err(i32::MIN));
+    }
+
+    #[test]
+    fn test_c_enum_names() {
+        assert_eq!(Color::Green.name(), c"Green");
+        assert_eq!(unsafe { CStr::from_ptr(color_name(Color::Blue)) }, c"Blue");
+        assert_eq!(color_name(Color::Blue), color_name(Color::Blue));
+        assert_eq!(
+            Color::VARIANTS,
+            [Color::Red, Color::Green, Color::Blue, Color::Unknown]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+use core::ffi::c_void;
+
+type Closure = Box<dyn FnMut()>;
+
+/// Moves `f` to C as a `void *context` plus a trampoline to call it with, for C
+/// APIs that take a callback as a function pointer and a context pointer.
+///
+/// The trampoline may be called any number of times with the context. The
+/// closure is dropped by passing the context to `drop_closure`, after which
+/// neither may be used again. A panic in `f` aborts, as it cannot unwind into C.
+pub fn box_closure<F>(f: F) -> (*mut c_void, extern "C" fn(*mut c_void))
+where
+    F: FnMut() + 'static,
+{
+    let closure: Closure = Box::new(f);
+    (
+        Box::into_raw(Box::new(closure)) as *mut c_void,
+        call_closure,
+    )
+}
+
+/// Calls the closure behind a context from `box_closure`. A null `context` is ignored.
+extern "C" fn call_closure(context: *mut c_void) {
+    if let Some(closure) = unsafe { (context as *mut Closure).as_mut() } {
+        closure();
+    }
+}
+
+/// Drops the closure behind a context from `box_closure`. A null `context` is ignored.
+#[unsafe(export_name = ffi_symbol!("drop_closure"))]
+pub extern "C" fn drop_closure(context: *mut c_void) {
+    if !context.is_null() {
+        let _ = unsafe { Box::from_raw(context as *mut Closure) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_closure_mutates_captured_state() {
+        let calls = Rc::new(Cell::new(0));
+        let captured = calls.clone();
+        let mut total = 0;
+        let (context, trampoline) = box_closure(move || {
+            total += 10;
+            captured.set(total);
+        });
+
+        trampoline(context);
+        trampoline(context);
+        assert_eq!(calls.get(), 20);
+
+        drop_closure(context);
+        // The captured clone was dropped with the closure
+        assert_eq!(Rc::strong_count(&calls), 1);
+    }
+
+    #[test]
+    fn test_closure_null_context() {
+        let (context, trampoline) = box_closure(|| {});
+
+        trampoline(std::ptr::null_mut());
+        drop_closure(std::ptr::null_mut());
+        drop_closure(context);
+    }
+}
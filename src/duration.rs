@@ -0,0 +1,165 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// A C representation of a `Duration` as whole `seconds` plus `nanos`, e.g. for
+/// passing a timeout that ends in an `ErrorCode::TimeoutError`.
+///
+/// C may pass a `nanos` of one second or more; the excess is carried into
+/// `seconds` rather than rejected.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExternDuration {
+    pub seconds: u64,
+    pub nanos: u32,
+}
+
+impl ExternDuration {
+    /// Converts into a `Duration`, normalizing `nanos` of one second or more.
+    /// A total that does not fit saturates to `Duration::MAX`.
+    pub fn to_duration(&self) -> Duration {
+        let carry = u64::from(self.nanos / NANOS_PER_SEC);
+        match self.seconds.checked_add(carry) {
+            Some(seconds) => Duration::new(seconds, self.nanos % NANOS_PER_SEC),
+            None => Duration::MAX,
+        }
+    }
+
+    /// Converts from a `Duration`. The result always has `nanos` below one second.
+    pub fn from_duration(duration: Duration) -> Self {
+        ExternDuration {
+            seconds: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+        }
+    }
+}
+
+impl From<Duration> for ExternDuration {
+    fn from(duration: Duration) -> Self {
+        ExternDuration::from_duration(duration)
+    }
+}
+
+impl From<ExternDuration> for Duration {
+    fn from(duration: ExternDuration) -> Self {
+        duration.to_duration()
+    }
+}
+
+/// Converts a C `time_t`, whole seconds since the Unix epoch, into a `SystemTime`.
+/// Negative values are times before the epoch.
+///
+/// # Panics
+///
+/// Panics if the time cannot be represented by `SystemTime` on this platform.
+#[cfg(feature = "std")]
+pub fn time_t_to_system_time(t: i64) -> SystemTime {
+    let offset = Duration::from_secs(t.unsigned_abs());
+    if t < 0 {
+        UNIX_EPOCH - offset
+    } else {
+        UNIX_EPOCH + offset
+    }
+}
+
+/// Converts a `SystemTime` into a C `time_t`, rounding down to whole seconds like
+/// C does, so half a second before the epoch becomes -1. Times beyond the range
+/// of `i64` saturate.
+#[cfg(feature = "std")]
+pub fn system_time_to_time_t(t: SystemTime) -> i64 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+        Err(before) => {
+            let before = before.duration();
+            let seconds = before.as_secs() + u64::from(before.subsec_nanos() > 0);
+            0i64.checked_sub_unsigned(seconds).unwrap_or(i64::MIN)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_round_trip() {
+        let duration = Duration::new(30, 250_000_000);
+        let extern_duration = ExternDuration::from_duration(duration);
+
+        assert_eq!(
+            extern_duration,
+            ExternDuration {
+                seconds: 30,
+                nanos: 250_000_000,
+            }
+        );
+        assert_eq!(extern_duration.to_duration(), duration);
+        assert_eq!(Duration::from(ExternDuration::from(duration)), duration);
+    }
+
+    #[test]
+    fn test_duration_zero() {
+        assert_eq!(ExternDuration::default().to_duration(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_duration_overflowing_nanos_carries_into_seconds() {
+        let extern_duration = ExternDuration {
+            seconds: 1,
+            nanos: 2_500_000_000,
+        };
+
+        assert_eq!(extern_duration.to_duration(), Duration::from_millis(3_500));
+    }
+
+    #[test]
+    fn test_duration_overflowing_nanos_saturates() {
+        let extern_duration = ExternDuration {
+            seconds: u64::MAX,
+            nanos: u32::MAX,
+        };
+
+        assert_eq!(extern_duration.to_duration(), Duration::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_time_t_epoch() {
+        assert_eq!(time_t_to_system_time(0), UNIX_EPOCH);
+        assert_eq!(system_time_to_time_t(UNIX_EPOCH), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_time_t_positive() {
+        let t = time_t_to_system_time(1_700_000_000);
+
+        assert_eq!(t, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert_eq!(system_time_to_time_t(t), 1_700_000_000);
+        assert_eq!(
+            system_time_to_time_t(t + Duration::from_millis(999)),
+            1_700_000_000
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_time_t_before_epoch() {
+        // 1969-07-20 20:17:40 UTC
+        let t = time_t_to_system_time(-14_182_940);
+
+        assert_eq!(t, UNIX_EPOCH - Duration::from_secs(14_182_940));
+        assert_eq!(system_time_to_time_t(t), -14_182_940);
+        // Partial seconds round down, away from the epoch
+        assert_eq!(
+            system_time_to_time_t(UNIX_EPOCH - Duration::from_millis(500)),
+            -1
+        );
+    }
+}
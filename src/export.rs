@@ -0,0 +1,166 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::any::Any;
+use std::os::raw::c_char;
+
+use crate::string::c_char_to_string;
+
+/// A Rust parameter type that `ffi_export!` can receive from C.
+/// `CType` is the type used in the generated `extern "C"` signature.
+pub trait FfiArg {
+    type CType;
+
+    fn from_ffi(value: Self::CType) -> Self;
+}
+
+/// C strings are read with `c_char_to_string`; a null pointer reads as `""`.
+impl FfiArg for &str {
+    type CType = *const c_char;
+
+    fn from_ffi(value: *const c_char) -> Self {
+        if value.is_null() {
+            ""
+        } else {
+            c_char_to_string(value)
+        }
+    }
+}
+
+impl FfiArg for String {
+    type CType = *const c_char;
+
+    fn from_ffi(value: *const c_char) -> Self {
+        <&str as FfiArg>::from_ffi(value).to_owned()
+    }
+}
+
+macro_rules! impl_ffi_arg_for_scalars (
+    ($($t:ty),+) => ($(
+        impl FfiArg for $t {
+            type CType = $t;
+
+            fn from_ffi(value: $t) -> Self {
+                value
+            }
+        }
+    )+)
+);
+
+impl_ffi_arg_for_scalars!(
+    i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, bool
+);
+
+/// Builds the message for an error result from a caught panic payload.
+#[doc(hidden)]
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let detail = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("panic: {}", detail)
+}
+
+/// Wraps a Rust function returning `Result<T, E>` into a complete `extern "C"` entry point.
+///
+/// `ffi_export!(pub fn ffi_greet(name: &str, times: u32) => greet);` generates
+/// `ffi_greet(name: *const c_char, times: u32) -> *mut ExternResult`, which converts
+/// the arguments (see `FfiArg`), calls `greet(name, times)`, and returns its result
+/// as an `ExternResult`. A panic in `greet` is caught and reported as an
+/// `ErrorCode::Other` error instead of unwinding into C.
+#[macro_export]
+macro_rules! ffi_export (
+    ($vis:vis fn $name:ident($($arg:ident : $t:ty),* $(,)?) => $f:path) => (
+        #[unsafe(no_mangle)]
+        $vis extern "C" fn $name(
+            $($arg: <$t as $crate::export::FfiArg>::CType),*
+        ) -> *mut $crate::result::ExternResult {
+            $(let $arg = <$t as $crate::export::FfiArg>::from_ffi($arg);)*
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| $f($($arg),*))) {
+                Ok(result) => $crate::result::ExternResult::from(result).into_boxed_raw(),
+                Err(payload) => $crate::result::ExternResult::err(
+                    $crate::result::ErrorCode::Other,
+                    $crate::export::panic_message(payload),
+                ),
+            }
+        }
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::result::{ErrorCode, ExternResult, extern_result_into_result};
+    use std::ffi::CString;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct RepeatError;
+
+    impl fmt::Display for RepeatError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "times must be positive")
+        }
+    }
+
+    impl std::error::Error for RepeatError {}
+
+    fn repeat(word: &str, times: i32) -> Result<String, RepeatError> {
+        if times <= 0 {
+            return Err(RepeatError);
+        }
+        if word == "panic" {
+            panic!("refusing to repeat");
+        }
+        Ok(word.repeat(times as usize))
+    }
+
+    fn shout(word: String) -> Result<usize, RepeatError> {
+        Ok(word.to_uppercase().len())
+    }
+
+    ffi_export!(fn test_export_repeat(word: &str, times: i32) => repeat);
+    ffi_export!(pub fn test_export_shout(word: String) => shout);
+
+    fn call(word: &str, times: i32) -> *mut ExternResult {
+        let word = CString::new(word).unwrap();
+        test_export_repeat(word.as_ptr(), times)
+    }
+
+    #[test]
+    fn test_export_success() {
+        let result = unsafe { extern_result_into_result::<String>(call("ab", 3)) };
+
+        assert_eq!(*result.unwrap().unwrap(), "ababab");
+    }
+
+    #[test]
+    fn test_export_error() {
+        let error = unsafe { extern_result_into_result::<String>(call("ab", 0)) }.unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::Other);
+        assert_eq!(error.message, "times must be positive");
+    }
+
+    #[test]
+    fn test_export_panic_is_caught() {
+        let error = unsafe { extern_result_into_result::<String>(call("panic", 1)) }.unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::Other);
+        assert_eq!(error.message, "panic: refusing to repeat");
+    }
+
+    #[test]
+    fn test_export_owned_string_and_null_argument() {
+        let word = CString::new("quiet").unwrap();
+        let result =
+            unsafe { extern_result_into_result::<usize>(test_export_shout(word.as_ptr())) };
+        assert_eq!(*result.unwrap().unwrap(), 5);
+
+        // A null string argument reads as empty
+        let result =
+            unsafe { extern_result_into_result::<usize>(test_export_shout(std::ptr::null())) };
+        assert_eq!(*result.unwrap().unwrap(), 0);
+    }
+}
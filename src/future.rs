@@ -0,0 +1,113 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::result::ExternResult;
+
+/// Wakes the thread blocked in `block_on` by unparking it.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drives `fut` to completion on the calling thread, parking it while the
+/// future is pending.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Blocks the calling thread on `fut` and converts its output like
+/// `ExternResult::ok` and `ExternResult::from_unit_result`: the value is boxed
+/// on success, and an error becomes an error with the code from
+/// `default_error_code` carrying its message.
+///
+/// This uses a minimal built-in executor, so it suits futures that do not need
+/// a runtime, e.g. those awaiting channels. Futures tied to a runtime such as
+/// tokio must be driven by that runtime instead, e.g. with its `Handle::block_on`,
+/// and their output converted afterwards. Never call this from inside an async
+/// context, as it blocks the thread.
+pub fn block_on_to_result<F, T, E>(fut: F) -> *mut ExternResult
+where
+    F: Future<Output = Result<T, E>>,
+    E: std::error::Error,
+{
+    match block_on(fut) {
+        Ok(value) => ExternResult::ok(value),
+        Err(e) => ExternResult::err(crate::result::default_error_code(), e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{ErrorCode, OwnedExternResult, extern_result_into_result};
+    use std::fmt;
+    use std::future::{poll_fn, ready};
+
+    #[derive(Debug)]
+    struct TestError;
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Request failed")
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    #[test]
+    fn test_block_on_ready_ok() {
+        let result = block_on_to_result(ready(Ok::<_, TestError>(42u32)));
+
+        let value = unsafe { extern_result_into_result::<u32>(result) };
+        assert_eq!(value.unwrap().as_deref(), Some(&42));
+    }
+
+    #[test]
+    fn test_block_on_ready_err() {
+        let result = block_on_to_result(ready(Err::<u32, _>(TestError)));
+
+        let owned = unsafe { OwnedExternResult::from_raw(result) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert_eq!(error.message(), "Request failed");
+    }
+
+    #[test]
+    fn test_block_on_pending_future_is_woken() {
+        let mut polls = 0;
+        let fut = poll_fn(|cx| {
+            polls += 1;
+            if polls < 3 {
+                // Wake from another thread, as a reactor would
+                let waker = cx.waker().clone();
+                thread::spawn(move || waker.wake());
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok::<_, TestError>(String::from("done")))
+            }
+        });
+
+        let result = block_on_to_result(fut);
+
+        let value = unsafe { extern_result_into_result::<String>(result) };
+        assert_eq!(value.unwrap().as_deref().map(String::as_str), Some("done"));
+    }
+}
@@ -0,0 +1,122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+
+/// An opaque handle that lets C pull items from a Rust iterator one at a time
+/// instead of receiving them all at once.
+///
+/// #Safety
+///
+/// A handle must only be used from one thread at a time. Use `define_iterator!`
+/// to create the `next` and destructor functions for each item type.
+pub struct ExternIterator<T> {
+    inner: Box<dyn Iterator<Item = T>>,
+}
+
+impl<T> ExternIterator<T> {
+    /// Wraps `iter` in a handle for C. The handle must be released with the
+    /// destructor generated by `define_iterator!`.
+    pub fn new<I>(iter: I) -> *mut Self
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: 'static,
+    {
+        Box::into_raw(Box::new(ExternIterator {
+            inner: Box::new(iter.into_iter()),
+        }))
+    }
+
+    /// Advances the iterator.
+    pub fn next_item(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+}
+
+/// Creates the functions C needs to drive an `ExternIterator<$t>`:
+///
+/// - `$name_next(handle) -> *mut ExternResult` returns the next item as an `ok`
+///   result, or an `ok_null()` result once the iterator is exhausted. A null
+///   handle yields an `InvalidArgumentError`.
+/// - `$name_destroy(handle)` releases the handle and any remaining items.
+#[macro_export]
+macro_rules! define_iterator (
+    ($name:ident, $t:ty) => (
+        $crate::__paste! {
+            #[unsafe(no_mangle)]
+            pub extern "C" fn [<$name _next>](
+                handle: *mut $crate::iterator::ExternIterator<$t>,
+            ) -> *mut $crate::result::ExternResult {
+                match unsafe { handle.as_mut() } {
+                    Some(iter) => match iter.next_item() {
+                        Some(item) => $crate::result::ExternResult::ok(item),
+                        None => $crate::result::ExternResult::ok_null(),
+                    },
+                    None => $crate::result::ExternResult::err(
+                        $crate::result::ErrorCode::InvalidArgumentError,
+                        "Iterator handle is null",
+                    ),
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            pub extern "C" fn [<$name _destroy>](handle: *mut $crate::iterator::ExternIterator<$t>) {
+                if !handle.is_null() {
+                    let _ = unsafe { $crate::__alloc::boxed::Box::from_raw(handle) };
+                }
+            }
+        }
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::{ErrorCode, extern_result_into_result};
+
+    define_iterator!(test_u32_iter, u32);
+    define_iterator!(test_string_iter, String);
+
+    #[test]
+    fn test_iterator_to_exhaustion() {
+        let handle = ExternIterator::new(1..=3u32);
+
+        let mut pulled = Vec::new();
+        loop {
+            let item = unsafe { extern_result_into_result::<u32>(test_u32_iter_next(handle)) };
+            match item.unwrap() {
+                Some(value) => pulled.push(*value),
+                None => break,
+            }
+        }
+        assert_eq!(pulled, [1, 2, 3]);
+
+        // Stays exhausted
+        let item = unsafe { extern_result_into_result::<u32>(test_u32_iter_next(handle)) };
+        assert!(item.unwrap().is_none());
+
+        test_u32_iter_destroy(handle);
+    }
+
+    #[test]
+    fn test_iterator_is_lazy() {
+        let handle = ExternIterator::new((0..).map(|i| format!("item {}", i)));
+
+        let first = unsafe { extern_result_into_result::<String>(test_string_iter_next(handle)) };
+        assert_eq!(*first.unwrap().unwrap(), "item 0");
+
+        // Destroying an unfinished infinite iterator is fine
+        test_string_iter_destroy(handle);
+    }
+
+    #[test]
+    fn test_iterator_null_handle() {
+        let error =
+            unsafe { extern_result_into_result::<u32>(test_u32_iter_next(std::ptr::null_mut())) }
+                .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::InvalidArgumentError);
+        test_u32_iter_destroy(std::ptr::null_mut());
+    }
+}
@@ -0,0 +1,148 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use crate::result::ErrorCode;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(ErrorCode, CString)>> = const { RefCell::new(None) };
+}
+
+/// Records `code` and `msg` as the last error for the calling thread,
+/// replacing any previously recorded error.
+pub fn set_last_error<S>(code: ErrorCode, msg: S)
+where
+    S: Into<String>,
+{
+    let message = crate::string::to_c_string(msg.into());
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some((code, message)));
+}
+
+/// Clears the last error for the calling thread.
+#[unsafe(export_name = ffi_symbol!("ffi_clear_last_error"))]
+pub extern "C" fn ffi_clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Returns the code of the last error recorded on the calling thread.
+/// Returns `ErrorCode::Other` when no error has been recorded; check
+/// `ffi_last_error_message` for null to tell the two cases apart.
+#[unsafe(export_name = ffi_symbol!("ffi_last_error_code"))]
+pub extern "C" fn ffi_last_error_code() -> ErrorCode {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(ErrorCode::Other, |(code, _)| *code)
+    })
+}
+
+/// Returns the message of the last error recorded on the calling thread,
+/// or a null pointer if there is none.
+///
+/// #Safety
+///
+/// The returned string is owned by the error slot and must not be freed.
+/// It stays valid until the next call to `set_last_error` or
+/// `ffi_clear_last_error` on the same thread.
+#[unsafe(export_name = ffi_symbol!("ffi_last_error_message"))]
+pub extern "C" fn ffi_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |(_, message)| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::thread;
+
+    fn last_message() -> Option<String> {
+        let ptr = ffi_last_error_message();
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned())
+        }
+    }
+
+    #[test]
+    fn test_no_error_by_default() {
+        // A fresh thread has nothing recorded
+        thread::spawn(|| {
+            assert!(ffi_last_error_message().is_null());
+            assert_eq!(ffi_last_error_code(), ErrorCode::Other);
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_set_and_read_last_error() {
+        set_last_error(ErrorCode::NotFoundError, "Missing record");
+
+        assert_eq!(ffi_last_error_code(), ErrorCode::NotFoundError);
+        assert_eq!(last_message().as_deref(), Some("Missing record"));
+
+        ffi_clear_last_error();
+    }
+
+    #[test]
+    fn test_set_replaces_previous_error() {
+        set_last_error(ErrorCode::IoError, "First");
+        set_last_error(ErrorCode::TimeoutError, String::from("Second"));
+
+        assert_eq!(ffi_last_error_code(), ErrorCode::TimeoutError);
+        assert_eq!(last_message().as_deref(), Some("Second"));
+
+        ffi_clear_last_error();
+    }
+
+    #[test]
+    fn test_clear_last_error() {
+        set_last_error(ErrorCode::ValidationError, "Bad input");
+        ffi_clear_last_error();
+
+        assert!(ffi_last_error_message().is_null());
+    }
+
+    #[test]
+    fn test_threads_are_isolated() {
+        let first = thread::spawn(|| {
+            set_last_error(ErrorCode::NetworkError, "Connection refused");
+            // Give the other thread a chance to record its own error
+            thread::yield_now();
+            (ffi_last_error_code(), last_message())
+        });
+        let second = thread::spawn(|| {
+            set_last_error(ErrorCode::PermissionError, "Access denied");
+            thread::yield_now();
+            (ffi_last_error_code(), last_message())
+        });
+
+        let (first_code, first_msg) = first.join().unwrap();
+        let (second_code, second_msg) = second.join().unwrap();
+
+        assert_eq!(first_code, ErrorCode::NetworkError);
+        assert_eq!(first_msg.as_deref(), Some("Connection refused"));
+        assert_eq!(second_code, ErrorCode::PermissionError);
+        assert_eq!(second_msg.as_deref(), Some("Access denied"));
+    }
+
+    #[test]
+    fn test_error_on_other_thread_not_visible() {
+        ffi_clear_last_error();
+
+        thread::spawn(|| set_last_error(ErrorCode::Other, "Elsewhere"))
+            .join()
+            .unwrap();
+
+        assert!(ffi_last_error_message().is_null());
+    }
+}
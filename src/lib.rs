@@ -2,9 +2,139 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+// Without the default `std` feature only `core` and `alloc` are required, with
+// `export`, `future`, `last_error` and the `std::io` integrations compiled out.
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
 extern crate libc;
 
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+
+#[doc(hidden)]
+pub use paste::paste as __paste;
+
+/// Expands to the exported name of one of this crate's own C functions: `$name`
+/// as is, or prefixed with `ffitk_` under the `prefixed-symbols` feature to avoid
+/// link-time clashes with other statically linked libraries.
+#[cfg(feature = "prefixed-symbols")]
+macro_rules! ffi_symbol {
+    ($name:literal) => {
+        concat!("ffitk_", $name)
+    };
+}
+
+#[cfg(not(feature = "prefixed-symbols"))]
+macro_rules! ffi_symbol {
+    ($name:literal) => {
+        $name
+    };
+}
+
 #[macro_use]
 pub mod memory;
+#[macro_use]
+pub mod accessors;
+pub mod arena;
+#[macro_use]
+pub mod array;
+pub mod buffer;
+#[macro_use]
+pub mod c_enum;
+pub mod callback;
+pub mod duration;
+#[cfg(feature = "std")]
+#[macro_use]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod future;
+#[macro_use]
+pub mod iterator;
+#[cfg(feature = "std")]
+pub mod last_error;
+pub mod map;
+pub mod option;
+pub mod prelude;
 pub mod result;
+pub mod slice;
 pub mod string;
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::{CBufWriter, ExternBuffer};
+    use crate::result::{ErrorCode, ExternError, ExternResult};
+    use core::ffi::{c_char, c_void};
+
+    // Declares part of the exported surface the way a generated header does, so
+    // a symbol that is renamed, mangled or no longer exported fails to link.
+    // Each is linked by its exported name, prefixed or not.
+    unsafe extern "C" {
+        #[link_name = ffi_symbol!("destroy")]
+        fn destroy(obj: *mut c_void);
+        #[link_name = ffi_symbol!("destroy_c_char")]
+        fn destroy_c_char(s: *mut c_char);
+        #[link_name = ffi_symbol!("c_char_dup")]
+        fn c_char_dup(src: *const c_char) -> *mut c_char;
+        #[link_name = ffi_symbol!("c_char_eq")]
+        fn c_char_eq(a: *const c_char, b: *const c_char) -> bool;
+        #[link_name = ffi_symbol!("free_extern_error")]
+        fn free_extern_error(err: *mut ExternError);
+        #[link_name = ffi_symbol!("extern_result_destroy")]
+        fn extern_result_destroy(obj: *mut ExternResult);
+        #[link_name = ffi_symbol!("extern_result_is_ok")]
+        fn extern_result_is_ok(ptr: *const ExternResult) -> bool;
+        #[link_name = ffi_symbol!("extern_result_get_err")]
+        fn extern_result_get_err(ptr: *const ExternResult) -> *const ExternError;
+        #[link_name = ffi_symbol!("extern_buffer_destroy")]
+        fn extern_buffer_destroy(buf: ExternBuffer);
+        #[link_name = ffi_symbol!("drop_closure")]
+        fn drop_closure(context: *mut c_void);
+        #[link_name = ffi_symbol!("result_arena_new")]
+        fn result_arena_new() -> *mut c_void;
+        #[link_name = ffi_symbol!("result_arena_destroy")]
+        fn result_arena_destroy(arena: *mut c_void);
+    }
+
+    #[test]
+    fn test_exported_symbols_link() {
+        let null = core::ptr::null_mut();
+        unsafe {
+            let copy = c_char_dup(c"symbol".as_ptr());
+            assert!(c_char_eq(copy, c"symbol".as_ptr()));
+            destroy_c_char(copy);
+
+            let result = ExternResult::err(ErrorCode::Other, "Failure");
+            assert!(!extern_result_is_ok(result));
+            assert!(!extern_result_get_err(result).is_null());
+            extern_result_destroy(result);
+
+            free_extern_error(core::ptr::null_mut());
+            drop_closure(null);
+            extern_buffer_destroy(CBufWriter::new().into_extern_buffer());
+            result_arena_destroy(result_arena_new());
+        }
+        // Not null-safe, so only its address is taken
+        core::hint::black_box(destroy as unsafe extern "C" fn(*mut c_void));
+    }
+
+    #[cfg(feature = "prefixed-symbols")]
+    #[test]
+    fn test_prefixed_symbols_link() {
+        unsafe extern "C" {
+            fn ffitk_c_char_dup(src: *const c_char) -> *mut c_char;
+            fn ffitk_destroy_c_char(s: *mut c_char);
+            fn ffitk_error_code_name(code: ErrorCode) -> *const c_char;
+        }
+
+        unsafe {
+            let copy = ffitk_c_char_dup(c"prefixed".as_ptr());
+            assert_eq!(core::ffi::CStr::from_ptr(copy), c"prefixed");
+            ffitk_destroy_c_char(copy);
+            assert_eq!(
+                core::ffi::CStr::from_ptr(ffitk_error_code_name(ErrorCode::IoError)),
+                c"IoError"
+            );
+        }
+    }
+}
@@ -0,0 +1,221 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ffi::c_char;
+
+use crate::string::string_to_c_char;
+
+/// A C representation of a string-to-string map as two parallel arrays:
+/// `keys[i]` is associated with `values[i]` for every `i < len`.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// A destructor `extern_kv_array_destroy` is provided for releasing the memory
+/// for this pointer type, including every key and value.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExternKvArray {
+    pub keys: *mut *mut c_char,
+    pub values: *mut *mut c_char,
+    pub len: usize,
+}
+
+impl ExternKvArray {
+    /// Builds an array from key/value pairs, e.g. a `HashMap<String, String>`.
+    /// The pairs keep the order in which `pairs` yields them.
+    pub fn new<I>(pairs: I) -> *mut Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let (keys, values): (Vec<_>, Vec<_>) = pairs
+            .into_iter()
+            .map(|(key, value)| (string_to_c_char(key), string_to_c_char(value)))
+            .unzip();
+        let len = keys.len();
+        Box::into_raw(Box::new(ExternKvArray {
+            keys: Box::into_raw(keys.into_boxed_slice()) as *mut *mut c_char,
+            values: Box::into_raw(values.into_boxed_slice()) as *mut *mut c_char,
+            len,
+        }))
+    }
+}
+
+/// Releases an `ExternKvArray`, both of its arrays and every key and value.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_kv_array_destroy"))]
+pub extern "C" fn extern_kv_array_destroy(arr: *mut ExternKvArray) {
+    if arr.is_null() {
+        return;
+    }
+    unsafe {
+        let arr = Box::from_raw(arr);
+        let keys = Box::from_raw(core::ptr::slice_from_raw_parts_mut(arr.keys, arr.len));
+        let values = Box::from_raw(core::ptr::slice_from_raw_parts_mut(arr.values, arr.len));
+        for s in keys.iter().chain(values.iter()) {
+            crate::memory::destroy_c_char(*s);
+        }
+    }
+}
+
+/// Returns the key at `index`, or null if `arr` is null or `index` is out of bounds.
+/// The returned string is owned by `arr` and must not be freed.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("kv_get_key"))]
+pub extern "C" fn kv_get_key(arr: *const ExternKvArray, index: usize) -> *const c_char {
+    match unsafe { arr.as_ref() } {
+        Some(arr) if index < arr.len => unsafe { *arr.keys.add(index) },
+        _ => core::ptr::null(),
+    }
+}
+
+/// Returns the value at `index`, or null if `arr` is null or `index` is out of bounds.
+/// The returned string is owned by `arr` and must not be freed.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("kv_get_value"))]
+pub extern "C" fn kv_get_value(arr: *const ExternKvArray, index: usize) -> *const c_char {
+    match unsafe { arr.as_ref() } {
+        Some(arr) if index < arr.len => unsafe { *arr.values.add(index) },
+        _ => core::ptr::null(),
+    }
+}
+
+/// Parses a config blob from C such as `"k1=v1\nk2=v2"` into a map.
+///
+/// Each line holds one `key=value` pair, split at the first `=` so values may
+/// contain `=`. Keys and values are trimmed of surrounding whitespace. Blank
+/// lines, lines starting with `#` and lines without an `=` are skipped, and a
+/// repeated key keeps its last value. Invalid UTF-8 is replaced with U+FFFD.
+/// Returns an empty map for a null `ptr`.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid NUL-terminated C string.
+#[cfg(feature = "std")]
+pub unsafe fn c_char_to_kv_map(ptr: *const c_char) -> std::collections::HashMap<String, String> {
+    let Some(config) = (!ptr.is_null()).then(|| unsafe { core::ffi::CStr::from_ptr(ptr) }) else {
+        return Default::default();
+    };
+    config
+        .to_string_lossy()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string::c_char_to_string;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_kv_array_round_trip() {
+        let mut map = HashMap::new();
+        map.insert(String::from("host"), String::from("localhost"));
+        map.insert(String::from("port"), String::from("8080"));
+        map.insert(String::from("name"), String::from("世界"));
+
+        let arr = ExternKvArray::new(map.clone());
+        assert_eq!(unsafe { (*arr).len }, 3);
+
+        let mut round_tripped = HashMap::new();
+        for i in 0..3 {
+            let key = c_char_to_string(kv_get_key(arr, i));
+            let value = c_char_to_string(kv_get_value(arr, i));
+            round_tripped.insert(key.to_owned(), value.to_owned());
+        }
+        assert_eq!(round_tripped, map);
+
+        extern_kv_array_destroy(arr);
+    }
+
+    #[test]
+    fn test_kv_array_preserves_order() {
+        let pairs = vec![
+            (String::from("b"), String::from("2")),
+            (String::from("a"), String::from("1")),
+            (String::from("c"), String::from("3")),
+        ];
+        let arr = ExternKvArray::new(pairs.clone());
+
+        for (i, (key, value)) in pairs.iter().enumerate() {
+            assert_eq!(c_char_to_string(kv_get_key(arr, i)), key);
+            assert_eq!(c_char_to_string(kv_get_value(arr, i)), value);
+        }
+
+        extern_kv_array_destroy(arr);
+    }
+
+    #[test]
+    fn test_kv_array_bounds_checked() {
+        let arr = ExternKvArray::new(vec![(String::from("k"), String::from("v"))]);
+
+        assert!(kv_get_key(arr, 1).is_null());
+        assert!(kv_get_value(arr, 100).is_null());
+        assert!(kv_get_key(std::ptr::null(), 0).is_null());
+        assert!(kv_get_value(std::ptr::null(), 0).is_null());
+
+        extern_kv_array_destroy(arr);
+    }
+
+    #[test]
+    fn test_kv_array_empty() {
+        let arr = ExternKvArray::new(HashMap::new());
+
+        assert_eq!(unsafe { (*arr).len }, 0);
+        assert!(kv_get_key(arr, 0).is_null());
+
+        extern_kv_array_destroy(arr);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_kv_map_parses_pairs() {
+        let config = c"host = localhost\nport=8080\r\n  name =  My App  \n";
+
+        let map = unsafe { c_char_to_kv_map(config.as_ptr()) };
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["host"], "localhost");
+        assert_eq!(map["port"], "8080");
+        assert_eq!(map["name"], "My App");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_kv_map_skips_comments_and_blank_lines() {
+        let config = c"# Connection settings\n\n   \nhost=db\n  # port=1\nnot a pair\n";
+
+        let map = unsafe { c_char_to_kv_map(config.as_ptr()) };
+
+        assert_eq!(
+            map,
+            HashMap::from([(String::from("host"), String::from("db"))])
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_kv_map_value_containing_equals() {
+        let config = c"url=postgres://db?sslmode=require\nempty=";
+
+        let map = unsafe { c_char_to_kv_map(config.as_ptr()) };
+
+        assert_eq!(map["url"], "postgres://db?sslmode=require");
+        assert_eq!(map["empty"], "");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_kv_map_null() {
+        assert!(unsafe { c_char_to_kv_map(std::ptr::null()) }.is_empty());
+    }
+}
@@ -2,18 +2,44 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::ffi::CString;
-use std::os::raw::{c_char, c_void};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::ffi::{c_char, c_void};
 
 /// Creates a function with a given `$name` that releases the memory for a type `$t`.
+/// An optional visibility and ABI may be given, e.g.
+/// `define_destructor!(pub my_free, Foo, "system")`; the ABI defaults to `"C"`.
+/// Only `pub` destructors are picked up by `cbindgen` when generating a header.
 #[macro_export]
 macro_rules! define_destructor (
-    ($name:ident, $t:ty) => (
+    ($vis:vis $name:ident, $t:ty, $abi:literal) => (
         #[unsafe(no_mangle)]
-        extern "C" fn $name(obj: *mut $t) {
-            let _ = unsafe{ Box::from_raw(obj) };
+        $vis extern $abi fn $name(obj: *mut $t) {
+            let _ = unsafe{ $crate::__alloc::boxed::Box::from_raw(obj) };
         }
-    )
+    );
+    ($vis:vis $name:ident, $t:ty) => (
+        $crate::define_destructor!($vis $name, $t, "C");
+    );
+);
+
+/// Creates a function with a given `$name` that boxes the value returned by
+/// `$builder` and hands ownership of it to C, the counterpart of `define_destructor`.
+/// Arguments may be declared after the name and are passed on to the builder, e.g.
+/// `define_constructor!(pub foo_new(id: u32), Foo, Foo::new)`.
+#[macro_export]
+macro_rules! define_constructor (
+    ($vis:vis $name:ident($($arg:ident: $arg_t:ty),* $(,)?), $t:ty, $builder:expr) => (
+        #[unsafe(no_mangle)]
+        $vis extern "C" fn $name($($arg: $arg_t),*) -> *mut $t {
+            let obj: $t = ($builder)($($arg),*);
+            $crate::__alloc::boxed::Box::into_raw($crate::__alloc::boxed::Box::new(obj))
+        }
+    );
+    ($vis:vis $name:ident, $t:ty, $builder:expr) => (
+        $crate::define_constructor!($vis $name(), $t, $builder);
+    );
 );
 
 /// Creates a function with a given `$name` that releases the memory
@@ -26,23 +52,373 @@ macro_rules! define_destructor (
 #[macro_export]
 macro_rules! define_destructor_with_lifetimes (
     ($name:ident, $t:ty) => (
-        #[no_mangle]
+        #[unsafe(no_mangle)]
         pub extern "C" fn $name<'a, 'c>(obj: *mut $t) {
-            let _ = unsafe{ Box::from_raw(obj) };
+            let _ = unsafe{ $crate::__alloc::boxed::Box::from_raw(obj) };
+        }
+    )
+);
+
+/// Creates a function with a given `$name` that releases an
+/// [ExternSlice](crate::slice::ExternSlice) of `$t` created by `ExternSlice::from_vec`.
+#[macro_export]
+macro_rules! define_slice_destructor (
+    ($name:ident, $t:ty) => (
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $name(slice: $crate::slice::ExternSlice<$t>) {
+            if !slice.data.is_null() {
+                let _ = unsafe { $crate::__alloc::vec::Vec::from_raw_parts(slice.data, slice.len, slice.len) };
+            }
+        }
+    )
+);
+
+/// Creates a function with a given `$name` that releases the backing store of a
+/// `Vec<$t>` handed to C as its raw `(ptr, len, cap)` parts, dropping the `len`
+/// initialized elements. A null `ptr` is ignored.
+///
+/// The allocator must be given back the exact capacity it allocated, so C has to
+/// pass along the `cap` it received from the constructor unchanged, even if it
+/// only reads `len` elements. Use `ExternSlice` instead when capacity need not
+/// be tracked, as it always shrinks to `len`.
+#[macro_export]
+macro_rules! define_vec_destructor (
+    ($name:ident, $t:ty) => (
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $name(ptr: *mut $t, len: usize, cap: usize) {
+            if !ptr.is_null() {
+                let _ = unsafe { $crate::__alloc::vec::Vec::from_raw_parts(ptr, len, cap) };
+            }
         }
     )
 );
 
-define_destructor!(destroy, c_void);
+/// Creates a function with a given `$name` that releases the memory for a type
+/// `$t` holding secrets, such as keys or passwords, wiping it before it is freed.
+/// An optional visibility may be given, as with `define_destructor!`.
+///
+/// Only the bytes of `$t` itself are wiped. Heap memory it owns, such as the
+/// buffer of a `String` field, is freed by its own `Drop` without being zeroed,
+/// so keep secrets inline, e.g. in a `[u8; 32]`, or wipe them in `Drop`.
+#[macro_export]
+macro_rules! define_zeroizing_destructor (
+    ($vis:vis $name:ident, $t:ty) => (
+        #[unsafe(no_mangle)]
+        $vis extern "C" fn $name(obj: *mut $t) {
+            unsafe { $crate::memory::zeroizing_free(obj) };
+        }
+    );
+);
+
+/// Frees `obj` as a `Box<c_void>`, i.e. a single unaligned byte. The original
+/// type is unknown here, so do not use this for boxed values of any other size or
+/// alignment, such as an `ExternResult::ok` value; use a typed destructor from
+/// `define_destructor!` or `define_result_destructor!` instead.
+#[allow(clippy::from_raw_with_void_ptr, clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("destroy"))]
+pub extern "C" fn destroy(obj: *mut c_void) {
+    let _ = unsafe { Box::from_raw(obj) };
+}
 
-#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("destroy_raw_uuid"))]
 pub extern "C" fn destroy_raw_uuid(obj: *mut [u8; 16]) {
     let _ = unsafe { Box::from_raw(obj) };
 }
 
-#[unsafe(no_mangle)]
+#[cfg(not(all(feature = "debug-guards", debug_assertions)))]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("destroy_c_char"))]
+pub extern "C" fn destroy_c_char(s: *mut c_char) {
+    let _ = unsafe { alloc::ffi::CString::from_raw(s) };
+}
+
+/// With `debug-guards`, the string is poisoned (emptied) and quarantined instead
+/// of freed, so a second call is ignored rather than corrupting the heap.
+#[cfg(all(feature = "debug-guards", debug_assertions))]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("destroy_c_char"))]
 pub extern "C" fn destroy_c_char(s: *mut c_char) {
-    let _ = unsafe { CString::from_raw(s) };
+    if !s.is_null() && guard::quarantine(s as *const c_void, "C string") {
+        unsafe { *s = 0 };
+    }
+}
+
+/// Frees `s` like `destroy_c_char`, but first overwrites the string with zeros,
+/// e.g. for passwords. A null `s` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("secure_destroy_c_char"))]
+pub extern "C" fn secure_destroy_c_char(s: *mut c_char) {
+    if s.is_null() {
+        return;
+    }
+    let mut bytes = unsafe { alloc::ffi::CString::from_raw(s) }.into_bytes_with_nul();
+    unsafe { zeroize_bytes(bytes.as_mut_ptr(), bytes.len()) };
+}
+
+/// Drops the boxed `T` at `obj`, overwrites its memory with zeros and only then
+/// frees it. This backs `define_zeroizing_destructor!`. A null `obj` is ignored.
+///
+/// # Safety
+///
+/// A non-null `obj` must have been allocated as a `Box<T>` and not freed since.
+pub unsafe fn zeroizing_free<T>(obj: *mut T) {
+    if obj.is_null() {
+        return;
+    }
+    unsafe {
+        core::ptr::drop_in_place(obj);
+        zeroize_bytes(obj as *mut u8, core::mem::size_of::<T>());
+        // Frees the allocation without dropping the value a second time
+        drop(Box::from_raw(obj as *mut core::mem::MaybeUninit<T>));
+    }
+}
+
+/// Overwrites `len` bytes at `ptr` with zeros. The writes are volatile, so they
+/// are not optimized away even when the memory is freed right afterwards.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` bytes.
+unsafe fn zeroize_bytes(ptr: *mut u8, len: usize) {
+    for i in 0..len {
+        unsafe { ptr.add(i).write_volatile(0) };
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+/// Leak detection for debug builds with the `leak-check` feature.
+///
+/// Every boxed `ExternResult` is counted when it is created and uncounted when
+/// one of this crate's destructors frees it, so a test can check that it freed
+/// every result it allocated. Without the feature, or in release builds, the
+/// counter does not exist and these functions do nothing.
+pub(crate) mod leak_check {
+    #[cfg(all(feature = "leak-check", debug_assertions))]
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    #[cfg(all(feature = "leak-check", debug_assertions))]
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+    /// Counts a new allocation.
+    pub(crate) fn register() {
+        #[cfg(all(feature = "leak-check", debug_assertions))]
+        LIVE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Uncounts a freed allocation. Results that were never counted, such as
+    /// ones boxed by hand, saturate at 0 rather than wrapping.
+    pub(crate) fn unregister() {
+        #[cfg(all(feature = "leak-check", debug_assertions))]
+        let _ = LIVE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+            Some(live.saturating_sub(1))
+        });
+    }
+
+    /// The number of counted allocations not yet freed.
+    #[cfg(feature = "leak-check")]
+    pub(crate) fn live() -> usize {
+        #[cfg(debug_assertions)]
+        return LIVE.load(Ordering::Relaxed);
+        #[cfg(not(debug_assertions))]
+        0
+    }
+}
+
+/// Returns the number of `ExternResult`s created by this crate that have not
+/// been freed yet, as a leak tripwire for tests: after a test frees everything
+/// it allocated, the count should be back where it started.
+///
+/// Only the outer `ExternResult` boxes are counted. A leaked `ok` value, an
+/// `ExternError` freed separately with `free_extern_error`, or a string or
+/// buffer is not seen here.
+///
+/// The count is process-wide, so compare it only while no other thread creates
+/// or frees results. It is always 0 in release builds.
+#[cfg(feature = "leak-check")]
+#[unsafe(export_name = ffi_symbol!("ffi_live_result_count"))]
+pub extern "C" fn ffi_live_result_count() -> usize {
+    leak_check::live()
+}
+
+/// Double-free detection for debug builds with the `debug-guards` feature.
+///
+/// Guarded destructors poison their allocation and hand its address to
+/// `quarantine` instead of releasing it. Because quarantined memory is never
+/// returned to the allocator, its address cannot be reused, so a repeated
+/// destructor call is detected reliably. The cost is that guarded allocations
+/// are leaked, which is why this is never enabled in release builds.
+#[cfg(all(feature = "debug-guards", debug_assertions))]
+pub(crate) mod guard {
+    use std::collections::HashSet;
+    use std::os::raw::c_void;
+    use std::sync::{Mutex, OnceLock};
+
+    fn quarantined() -> &'static Mutex<HashSet<usize>> {
+        static FREED: OnceLock<Mutex<HashSet<usize>>> = OnceLock::new();
+        FREED.get_or_init(Default::default)
+    }
+
+    /// Records `ptr` as freed. Returns `false` if it had already been freed,
+    /// logging the double free of `what` as an error with the `log` feature.
+    pub(crate) fn quarantine(ptr: *const c_void, what: &str) -> bool {
+        let newly_freed = quarantined()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(ptr as usize);
+        #[cfg(feature = "log")]
+        if !newly_freed {
+            log::error!("Double free of {} at {:p} ignored", what, ptr);
+        }
+        #[cfg(not(feature = "log"))]
+        let _ = what;
+        newly_freed
+    }
+
+    /// Returns `true` if `ptr` has been passed to a guarded destructor.
+    #[cfg(test)]
+    pub(crate) fn is_quarantined(ptr: *const c_void) -> bool {
+        quarantined()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&(ptr as usize))
+    }
+}
+
+/// A raw pointer that may be sent to and shared between threads, e.g. to park an
+/// `*mut ExternResult` in a channel in async FFI glue.
+///
+/// Raw pointers are neither `Send` nor `Sync` because the compiler cannot know who
+/// else can reach the pointee. `SendPtr` is an explicit, opt-in promise from the
+/// caller that moving the pointer across threads is sound.
+#[derive(Debug)]
+pub struct SendPtr<T>(*mut T);
+
+impl<T> SendPtr<T> {
+    /// Wraps `ptr` so it can cross thread boundaries.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that:
+    /// - the pointee is not accessed through any other pointer while this one is
+    ///   in use on another thread (ownership is effectively transferred), and
+    /// - the pointee itself is safe to use from another thread, i.e. it would be
+    ///   `Send` (and `Sync` if the `SendPtr` is shared) were it not behind a raw
+    ///   pointer. Types with thread affinity, such as `Rc` or thread-local
+    ///   handles, must never be wrapped.
+    pub unsafe fn new(ptr: *mut T) -> Self {
+        SendPtr(ptr)
+    }
+
+    /// Returns the wrapped pointer.
+    pub fn as_ptr(&self) -> *mut T {
+        self.0
+    }
+
+    /// Unwraps the pointer.
+    pub fn into_inner(self) -> *mut T {
+        self.0
+    }
+}
+
+// SAFETY: upheld by the caller of `SendPtr::new`.
+unsafe impl<T> Send for SendPtr<T> {}
+// SAFETY: upheld by the caller of `SendPtr::new`.
+unsafe impl<T> Sync for SendPtr<T> {}
+
+/// Hands a reference-counted `T` to C as an opaque handle, for objects shared by
+/// several C owners. Each owner takes its own reference with `arc_clone_raw` and
+/// releases it with `arc_free_raw`; `T` is dropped when the last one is freed.
+///
+/// All three functions must be used with the same `T` for a given handle.
+pub fn arc_into_raw<T>(arc: Arc<T>) -> *const T {
+    Arc::into_raw(arc)
+}
+
+/// Takes another reference to the handle `ptr` and returns it. A null `ptr`
+/// returns null.
+///
+/// # Safety
+///
+/// A non-null `ptr` must come from `arc_into_raw::<T>` or `arc_clone_raw::<T>`
+/// and still hold a reference that has not been freed.
+pub unsafe fn arc_clone_raw<T>(ptr: *const T) -> *const T {
+    if !ptr.is_null() {
+        unsafe { Arc::increment_strong_count(ptr) };
+    }
+    ptr
+}
+
+/// Releases one reference to the handle `ptr`, dropping `T` if it was the last.
+/// A null `ptr` is ignored.
+///
+/// # Safety
+///
+/// A non-null `ptr` must come from `arc_into_raw::<T>` or `arc_clone_raw::<T>`,
+/// and each reference must be freed exactly once.
+pub unsafe fn arc_free_raw<T>(ptr: *const T) {
+    if !ptr.is_null() {
+        unsafe { Arc::decrement_strong_count(ptr) };
+    }
+}
+
+/// Hands a trait object to C as a thin, opaque handle, e.g. a
+/// `Box<dyn Iterator<Item = i32>>` whose methods C calls through `extern "C"`
+/// trampolines.
+///
+/// A `Box<dyn Trait>` is a fat pointer, a data pointer plus a vtable pointer, so it
+/// does not fit in a `*mut c_void`. It is therefore boxed a second time: the handle
+/// points to a heap-allocated `Box<dyn Trait>`, which keeps both halves intact.
+///
+/// `trait_object_from_handle` and `trait_object_free_handle` must be used with
+/// the same `T` as the handle was created with, e.g. `dyn Iterator<Item = i32>`.
+pub fn trait_object_into_handle<T: ?Sized>(obj: Box<T>) -> *mut c_void {
+    Box::into_raw(Box::new(obj)) as *mut c_void
+}
+
+/// Borrows the trait object behind `handle`, e.g. inside a trampoline, or returns
+/// `None` for a null handle.
+///
+/// # Safety
+///
+/// A non-null `handle` must come from `trait_object_into_handle::<T>`, must not
+/// have been freed, and must not be borrowed elsewhere while the reference lives.
+pub unsafe fn trait_object_from_handle<'a, T: ?Sized>(handle: *mut c_void) -> Option<&'a mut T> {
+    unsafe { (handle as *mut Box<T>).as_mut() }.map(|obj| &mut **obj)
+}
+
+/// Drops the trait object behind `handle` and frees both boxes. A null `handle`
+/// is ignored.
+///
+/// # Safety
+///
+/// A non-null `handle` must come from `trait_object_into_handle::<T>` and must be
+/// freed exactly once.
+pub unsafe fn trait_object_free_handle<T: ?Sized>(handle: *mut c_void) {
+    if !handle.is_null() {
+        let _ = unsafe { Box::from_raw(handle as *mut Box<T>) };
+    }
+}
+
+/// Creates a function with a given `$name` that frees a trait object handle made
+/// by `trait_object_into_handle` for the trait object type `$t`, e.g.
+/// `define_trait_object_handle!(counter_destroy, dyn Iterator<Item = i32>);`.
+/// A null handle is ignored.
+#[macro_export]
+macro_rules! define_trait_object_handle (
+    ($name:ident, $t:ty) => (
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $name(handle: *mut ::core::ffi::c_void) {
+            unsafe { $crate::memory::trait_object_free_handle::<$t>(handle) };
+        }
+    );
+);
+
+/// The layout of `len` contiguous `T`s, or `None` if its size would overflow
+/// `isize`. `slice::from_raw_parts` requires that it never does, so every
+/// conversion from a C-provided `{ ptr, len }` pair checks this first.
+pub(crate) fn checked_layout<T>(len: usize) -> Option<Layout> {
+    Layout::array::<T>(len).ok()
 }
 
 #[macro_export]
@@ -52,9 +428,51 @@ macro_rules! assert_pointer_not_null {
     )+);
 }
 
+/// Panics in debug builds if the pointer `$e` is not aligned for `$t`, e.g. one
+/// taken from a field of a packed C struct. Dereferencing such a pointer is
+/// undefined behavior even when it is non-null. A null pointer passes, so this
+/// can run before the null check.
+#[macro_export]
+macro_rules! assert_aligned {
+    ($e:expr, $t:ty) => (
+        debug_assert!(
+            ($e as usize) % ::core::mem::align_of::<$t>() == 0,
+            "Misaligned pointer: {} is {:#x}, which is not aligned to {} bytes for `{}`",
+            stringify!($e),
+            $e as usize,
+            ::core::mem::align_of::<$t>(),
+            stringify!($t),
+        );
+    );
+}
+
+/// Borrows the `T` behind a pointer from C, or returns `None` if it is null.
+/// Use this for optional arguments and `assert_pointer_not_null!` for required ones.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to a valid, aligned `T` that outlives `'a` and is
+/// not mutated while the reference lives.
+pub unsafe fn opt_ref<'a, T>(ptr: *const T) -> Option<&'a T> {
+    assert_aligned!(ptr, T);
+    unsafe { ptr.as_ref() }
+}
+
+/// Mutably borrows the `T` behind a pointer from C, or returns `None` if it is null.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to a valid, aligned `T` that outlives `'a` and is
+/// not accessed through any other pointer while the reference lives.
+pub unsafe fn opt_ref_mut<'a, T>(ptr: *mut T) -> Option<&'a mut T> {
+    assert_aligned!(ptr, T);
+    unsafe { ptr.as_mut() }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ffi::CString;
     use std::ptr;
 
     // Test structure for destructor macro testing
@@ -67,6 +485,57 @@ mod tests {
     // Define a custom destructor for our test struct
     define_destructor!(destroy_test_struct, TestStruct);
 
+    pub struct PublicTestStruct {
+        pub value: i32,
+    }
+
+    define_destructor!(pub destroy_public_test_struct, PublicTestStruct);
+    define_destructor!(pub destroy_system_test_struct, PublicTestStruct, "system");
+
+    define_constructor!(new_test_struct(value: i32), TestStruct, |value| TestStruct {
+        value,
+        name: String::from("constructed"),
+    });
+    define_constructor!(pub new_public_test_struct, PublicTestStruct, || PublicTestStruct {
+        value: 7
+    });
+
+    define_vec_destructor!(destroy_u32_vec, u32);
+
+    pub struct DropCounter(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    define_vec_destructor!(destroy_drop_counter_vec, DropCounter);
+
+    #[test]
+    fn test_vec_destructor_preserves_capacity() {
+        let mut values = Vec::with_capacity(16);
+        values.extend([1u32, 2, 3]);
+        let mut values = std::mem::ManuallyDrop::new(values);
+        let (ptr, len, cap) = (values.as_mut_ptr(), values.len(), values.capacity());
+        assert!(cap > len);
+
+        destroy_u32_vec(ptr, len, cap);
+        destroy_u32_vec(std::ptr::null_mut(), 0, 0);
+    }
+
+    #[test]
+    fn test_vec_destructor_drops_elements() {
+        let drops = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut counters = Vec::with_capacity(4);
+        counters.extend([DropCounter(drops.clone()), DropCounter(drops.clone())]);
+        let mut counters = std::mem::ManuallyDrop::new(counters);
+
+        destroy_drop_counter_vec(counters.as_mut_ptr(), counters.len(), counters.capacity());
+
+        assert_eq!(drops.get(), 2);
+    }
+
     #[test]
     fn test_destroy_test_struct_valid_pointer() {
         // Create a boxed value and convert to raw pointer
@@ -110,6 +579,19 @@ mod tests {
         destroy_c_char(raw_ptr);
     }
 
+    #[test]
+    #[cfg(all(feature = "debug-guards", debug_assertions))]
+    fn test_destroy_c_char_double_free_detected() {
+        let raw_ptr = CString::new("freed twice").unwrap().into_raw();
+
+        destroy_c_char(raw_ptr);
+        assert!(guard::is_quarantined(raw_ptr as *const c_void));
+        // Without the guard this would be a double free
+        destroy_c_char(raw_ptr);
+
+        assert!(!guard::quarantine(raw_ptr as *const c_void, "C string"));
+    }
+
     #[test]
     fn test_destroy_c_char_empty_string() {
         // Test with empty string
@@ -166,6 +648,98 @@ mod tests {
         assert_pointer_not_null!(null_ptr);
     }
 
+    #[test]
+    fn test_opt_ref() {
+        let value = TestStruct {
+            value: 7,
+            name: String::from("seven"),
+        };
+
+        let borrowed = unsafe { opt_ref(&value as *const TestStruct) };
+        assert_eq!(borrowed, Some(&value));
+        assert_eq!(unsafe { opt_ref::<TestStruct>(ptr::null()) }, None);
+    }
+
+    #[test]
+    fn test_opt_ref_mut() {
+        let mut value = 41;
+
+        if let Some(value) = unsafe { opt_ref_mut(&mut value as *mut i32) } {
+            *value += 1;
+        }
+        assert_eq!(value, 42);
+        assert!(unsafe { opt_ref_mut::<i32>(ptr::null_mut()) }.is_none());
+    }
+
+    #[test]
+    fn test_assert_aligned_valid() {
+        let value = 42u64;
+        let ptr = &value as *const u64;
+
+        // Should not panic, and neither should a null pointer
+        assert_aligned!(ptr, u64);
+        assert_aligned!(ptr::null::<u64>(), u64);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "Misaligned pointer: misaligned")]
+    fn test_assert_aligned_panics_on_misaligned() {
+        let values = [0u64; 2];
+        let misaligned = unsafe { (values.as_ptr() as *const u8).add(1) } as *const u64;
+        assert_aligned!(misaligned, u64);
+    }
+
+    define_trait_object_handle!(test_counter_destroy, dyn Iterator<Item = i32>);
+
+    /// A trampoline as C would call it: advances the iterator behind `handle`.
+    extern "C" fn test_counter_next(handle: *mut c_void, out: *mut i32) -> bool {
+        let iter = unsafe { trait_object_from_handle::<dyn Iterator<Item = i32>>(handle) };
+        match iter.and_then(Iterator::next) {
+            Some(value) => {
+                unsafe { *out = value };
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn test_trait_object_handle() {
+        let counter: Box<dyn Iterator<Item = i32>> = Box::new((1..=3).map(|i| i * 10));
+        let handle = trait_object_into_handle(counter);
+
+        let mut values = Vec::new();
+        let mut value = 0;
+        while test_counter_next(handle, &mut value) {
+            values.push(value);
+        }
+        assert_eq!(values, [10, 20, 30]);
+
+        test_counter_destroy(handle);
+        test_counter_destroy(ptr::null_mut());
+        assert!(!test_counter_next(ptr::null_mut(), &mut value));
+    }
+
+    #[test]
+    fn test_trait_object_handle_drops_object() {
+        struct Flag(std::rc::Rc<std::cell::Cell<bool>>);
+
+        impl Drop for Flag {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let dropped = std::rc::Rc::new(std::cell::Cell::new(false));
+        let obj: Box<dyn std::any::Any> = Box::new(Flag(dropped.clone()));
+        let handle = trait_object_into_handle(obj);
+
+        assert!(!dropped.get());
+        unsafe { trait_object_free_handle::<dyn std::any::Any>(handle) };
+        assert!(dropped.get());
+    }
+
     // Test to verify macro-generated function has correct signature
     #[test]
     fn test_destructor_macro_generates_extern_c_function() {
@@ -182,6 +756,43 @@ mod tests {
         test_fn(ptr);
     }
 
+    #[test]
+    fn test_destructor_macro_pub_visibility() {
+        // A `pub` destructor can be re-exported from the enclosing module
+        let test_fn: extern "C" fn(*mut PublicTestStruct) =
+            super::tests::destroy_public_test_struct;
+
+        let ptr = Box::into_raw(Box::new(PublicTestStruct { value: 1 }));
+        test_fn(ptr);
+    }
+
+    #[test]
+    fn test_destructor_macro_custom_abi() {
+        let test_fn: extern "system" fn(*mut PublicTestStruct) = destroy_system_test_struct;
+
+        let ptr = Box::into_raw(Box::new(PublicTestStruct { value: 2 }));
+        assert_eq!(unsafe { (*ptr).value }, 2);
+        test_fn(ptr);
+    }
+
+    #[test]
+    fn test_constructor_and_destructor_pair() {
+        let ptr = new_test_struct(42);
+        assert!(!ptr.is_null());
+        assert_eq!(
+            unsafe { &*ptr },
+            &TestStruct {
+                value: 42,
+                name: String::from("constructed"),
+            }
+        );
+        destroy_test_struct(ptr);
+
+        let public_ptr = new_public_test_struct();
+        assert_eq!(unsafe { (*public_ptr).value }, 7);
+        destroy_public_test_struct(public_ptr);
+    }
+
     // Test memory safety: ensure we can create and destroy multiple objects
     #[test]
     fn test_multiple_allocations_and_destructions() {
@@ -195,6 +806,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_arc_handle_dropped_once_after_last_free() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Shared<'a>(&'a AtomicUsize);
+
+        impl Drop for Shared<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let drops = AtomicUsize::new(0);
+        let handle = arc_into_raw(Arc::new(Shared(&drops)));
+        let second = unsafe { arc_clone_raw(handle) };
+        let third = unsafe { arc_clone_raw(second) };
+        assert_eq!(handle, third);
+
+        unsafe {
+            arc_free_raw(handle);
+            arc_free_raw(second);
+            assert_eq!(drops.load(Ordering::SeqCst), 0);
+            arc_free_raw(third);
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_checked_layout() {
+        assert_eq!(checked_layout::<u64>(4).unwrap().size(), 32);
+        assert!(checked_layout::<u8>(isize::MAX as usize).is_some());
+        assert!(checked_layout::<u8>(isize::MAX as usize + 1).is_none());
+        assert!(checked_layout::<u16>(usize::MAX / 2).is_none());
+    }
+
+    #[test]
+    fn test_arc_handle_null() {
+        unsafe {
+            assert!(arc_clone_raw::<u32>(ptr::null()).is_null());
+            arc_free_raw::<u32>(ptr::null());
+        }
+    }
+
+    #[test]
+    fn test_send_ptr_moves_result_across_threads() {
+        use crate::result::ExternResult;
+
+        let result = unsafe { SendPtr::new(ExternResult::ok(42i32)) };
+
+        std::thread::spawn(move || {
+            let result_ptr = result.into_inner();
+            unsafe {
+                let result = &*result_ptr;
+                assert_eq!(*(result.ok as *const i32), 42);
+
+                let _ = Box::from_raw(result.ok as *mut i32);
+                let _ = Box::from_raw(result_ptr);
+            }
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_send_ptr_through_channel() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let value = Box::into_raw(Box::new(String::from("parked")));
+
+        sender.send(unsafe { SendPtr::new(value) }).unwrap();
+        let received = std::thread::spawn(move || receiver.recv().unwrap())
+            .join()
+            .unwrap();
+
+        assert_eq!(received.as_ptr(), value);
+        let value = unsafe { Box::from_raw(received.into_inner()) };
+        assert_eq!(*value, "parked");
+    }
+
     // Test with different primitive types
     #[test]
     fn test_destroy_various_types() {
@@ -204,7 +893,7 @@ mod tests {
         destroy(ptr_u64);
 
         // Test with f64
-        let val_f64 = Box::new(3.14159f64);
+        let val_f64 = Box::new(std::f64::consts::PI);
         let ptr_f64 = Box::into_raw(val_f64) as *mut c_void;
         destroy(ptr_f64);
 
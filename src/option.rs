@@ -0,0 +1,106 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Creates a `#[repr(C)]` nullable integer type `$name` for `Option<$t>`.
+///
+/// `has_value` is 1 when the option holds a value and 0 otherwise, in which
+/// case `value` is 0 and must be ignored.
+macro_rules! define_extern_option (
+    ($(#[$meta:meta])* $name:ident, $t:ty) => (
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name {
+            pub has_value: u8,
+            pub value: $t,
+        }
+
+        impl $name {
+            /// The value, or `None` if there is none.
+            pub fn get(&self) -> Option<$t> {
+                (self.has_value != 0).then_some(self.value)
+            }
+        }
+
+        impl From<Option<$t>> for $name {
+            fn from(option: Option<$t>) -> Self {
+                match option {
+                    Some(value) => $name { has_value: 1, value },
+                    None => $name { has_value: 0, value: 0 },
+                }
+            }
+        }
+
+        impl From<$name> for Option<$t> {
+            fn from(option: $name) -> Self {
+                option.get()
+            }
+        }
+    )
+);
+
+define_extern_option!(
+    /// A C representation of `Option<u64>`.
+    ExternOptionU64,
+    u64
+);
+define_extern_option!(
+    /// A C representation of `Option<i64>`.
+    ExternOptionI64,
+    i64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_option_u64_some() {
+        let option = ExternOptionU64::from(Some(u64::MAX));
+
+        assert_eq!(option.has_value, 1);
+        assert_eq!(option.value, u64::MAX);
+        assert_eq!(option.get(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_option_u64_none() {
+        let option = ExternOptionU64::from(None);
+
+        assert_eq!(option.has_value, 0);
+        assert_eq!(option.value, 0);
+        assert_eq!(option.get(), None);
+    }
+
+    #[test]
+    fn test_option_u64_zero_is_not_none() {
+        // Unlike a sentinel, zero is a real value
+        let option = ExternOptionU64::from(Some(0));
+
+        assert_eq!(option.has_value, 1);
+        assert_eq!(option.get(), Some(0));
+    }
+
+    #[test]
+    fn test_option_i64_some_and_none() {
+        let some = ExternOptionI64::from(Some(-42));
+        let none = ExternOptionI64::from(None);
+
+        assert_eq!((some.has_value, some.value), (1, -42));
+        assert_eq!((none.has_value, none.value), (0, 0));
+        assert_eq!(Option::<i64>::from(some), Some(-42));
+        assert_eq!(Option::<i64>::from(none), None);
+    }
+
+    #[test]
+    fn test_option_ignores_value_when_flag_unset() {
+        // C may leave garbage in `value` when `has_value` is 0
+        let option = ExternOptionI64 {
+            has_value: 0,
+            value: 99,
+        };
+
+        assert_eq!(option.get(), None);
+    }
+}
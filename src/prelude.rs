@@ -0,0 +1,35 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The Rust-side types, helpers and macros most FFI wrappers need, for a single
+//! `use ffi_toolkit::prelude::*;`.
+
+pub use crate::arena::ResultArena;
+pub use crate::buffer::{CBufWriter, ExternBuffer, ExternBufferArray, with_byte_slice};
+pub use crate::duration::ExternDuration;
+pub use crate::map::ExternKvArray;
+pub use crate::memory::{opt_ref, opt_ref_mut};
+pub use crate::result::{
+    ErrorClassify, ErrorCode, ExternError, ExternErrorBuilder, ExternResult, OwnedExternError,
+    OwnedExternResult, extern_result_into_result, extern_result_into_unit, extern_result_take_ok,
+};
+pub use crate::slice::{ExternSlice, c_f64_array_to_vec, vec_f64_to_c_array};
+pub use crate::string::{
+    ExternString, c_char_to_opt_string, c_char_to_string, c_char_to_string_replaced,
+    opt_string_to_c_char, string_to_c_char, try_string_to_c_char,
+};
+
+pub use crate::{
+    assert_aligned, assert_pointer_not_null, define_c_enum, define_constructor, define_destructor,
+    define_fixed_array, define_getters, define_iterator, define_result_destructor,
+    define_scalar_result, define_slice_destructor, define_trait_object_handle,
+    define_vec_destructor, define_zeroizing_destructor,
+};
+
+#[cfg(feature = "std")]
+pub use crate::export::FfiArg;
+#[cfg(feature = "std")]
+pub use crate::ffi_export;
+#[cfg(feature = "std")]
+pub use crate::last_error::set_last_error;
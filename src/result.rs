@@ -2,36 +2,205 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std;
-use std::os::raw::{c_char, c_void};
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ffi::{CStr, c_char, c_void};
+use core::ptr::NonNull;
+
+use crate::buffer::ExternBuffer;
+
+define_c_enum!(
+    @name_fn [unsafe(export_name = ffi_symbol!("error_code_name"))]
+    /// Error codes that can be returned across the FFI boundary.
+    /// These codes provide a standardized way to communicate error types
+    /// between Rust and C/C++ code.
+    ///
+    /// `error_code_name` returns the name of a code to C.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+    pub enum ErrorCode {
+        /// Generic error for cases that don't fit other categories
+        Other = 0,
+        /// Authentication or authorization failed
+        AuthenticationError = 1,
+        /// Input validation failed (invalid format, out of range, etc.)
+        ValidationError = 2,
+        /// Requested resource or item was not found
+        NotFoundError = 3,
+        /// Operation not permitted due to insufficient permissions
+        PermissionError = 4,
+        /// Operation timed out
+        TimeoutError = 5,
+        /// Network-related error (connection failed, DNS error, etc.)
+        NetworkError = 6,
+        /// Invalid argument passed to function
+        InvalidArgumentError = 7,
+        /// I/O operation failed (file read/write, etc.)
+        IoError = 8,
+        /// Operation was cancelled by the user or host
+        CancelledError = 9,
+        /// Operation is not implemented
+        UnimplementedError = 10,
+    }
+);
+
+impl ErrorCode {
+    /// Maps a POSIX `errno` value to the closest error code, defaulting to `Other`.
+    pub fn from_errno(errno: i32) -> ErrorCode {
+        match errno {
+            libc::ENOENT => ErrorCode::NotFoundError,
+            libc::EACCES | libc::EPERM => ErrorCode::PermissionError,
+            libc::ETIMEDOUT => ErrorCode::TimeoutError,
+            libc::ECONNREFUSED
+            | libc::ECONNRESET
+            | libc::ECONNABORTED
+            | libc::ENETDOWN
+            | libc::ENETUNREACH
+            | libc::EHOSTUNREACH => ErrorCode::NetworkError,
+            libc::EINVAL => ErrorCode::InvalidArgumentError,
+            libc::EIO
+            | libc::ENOSPC
+            | libc::EROFS
+            | libc::EPIPE
+            | libc::EBADF
+            | libc::EISDIR
+            | libc::ENOTDIR
+            | libc::EMFILE => ErrorCode::IoError,
+            libc::ECANCELED => ErrorCode::CancelledError,
+            libc::ENOSYS => ErrorCode::UnimplementedError,
+            _ => ErrorCode::Other,
+        }
+    }
 
-/// Error codes that can be returned across the FFI boundary.
-/// These codes provide a standardized way to communicate error types
-/// between Rust and C/C++ code.
-#[repr(C)]
-#[derive(Debug)]
-pub enum ErrorCode {
-    /// Generic error for cases that don't fit other categories
-    Other,
-    /// Authentication or authorization failed
-    AuthenticationError,
-    /// Input validation failed (invalid format, out of range, etc.)
-    ValidationError,
-    /// Requested resource or item was not found
-    NotFoundError,
-    /// Operation not permitted due to insufficient permissions
-    PermissionError,
-    /// Operation timed out
-    TimeoutError,
-    /// Network-related error (connection failed, DNS error, etc.)
-    NetworkError,
-    /// Invalid argument passed to function
-    InvalidArgumentError,
-    /// I/O operation failed (file read/write, etc.)
-    IoError,
-}
-
-/// An error struct containing an error code and a description string.
+    /// Maps an error code to a representative POSIX `errno` value.
+    /// Codes without a natural counterpart map to `EINVAL` or `EIO`.
+    pub fn to_errno(self) -> i32 {
+        match self {
+            ErrorCode::Other => libc::EIO,
+            ErrorCode::AuthenticationError => libc::EACCES,
+            ErrorCode::ValidationError => libc::EINVAL,
+            ErrorCode::NotFoundError => libc::ENOENT,
+            ErrorCode::PermissionError => libc::EPERM,
+            ErrorCode::TimeoutError => libc::ETIMEDOUT,
+            ErrorCode::NetworkError => libc::ECONNREFUSED,
+            ErrorCode::InvalidArgumentError => libc::EINVAL,
+            ErrorCode::IoError => libc::EIO,
+            ErrorCode::CancelledError => libc::ECANCELED,
+            ErrorCode::UnimplementedError => libc::ENOSYS,
+        }
+    }
+
+    /// Encodes the code as a single byte, e.g. for a binary protocol header.
+    /// The byte is the explicit discriminant, so it never changes between versions.
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Decodes a byte from `to_u8`, or returns `None` for a byte that is not a code.
+    pub fn from_u8(b: u8) -> Option<ErrorCode> {
+        ErrorCode::try_from(i32::from(b)).ok()
+    }
+
+    /// Whether an operation that failed with this code may succeed if retried.
+    ///
+    /// `TimeoutError` and `NetworkError` are retryable; validation, permission,
+    /// lookup, cancellation and unimplemented errors are not. `IoError` and `Other`
+    /// cover both transient and permanent failures, so they default to not
+    /// retryable and can be changed with `set_retryable`.
+    pub fn is_retryable(self) -> bool {
+        match self {
+            ErrorCode::TimeoutError | ErrorCode::NetworkError => true,
+            ErrorCode::IoError => RETRY_IO_ERROR.load(core::sync::atomic::Ordering::Relaxed),
+            ErrorCode::Other => RETRY_OTHER.load(core::sync::atomic::Ordering::Relaxed),
+            ErrorCode::AuthenticationError
+            | ErrorCode::ValidationError
+            | ErrorCode::NotFoundError
+            | ErrorCode::PermissionError
+            | ErrorCode::InvalidArgumentError
+            | ErrorCode::CancelledError
+            | ErrorCode::UnimplementedError => false,
+        }
+    }
+
+    /// Maps an error code to the HTTP status a service would respond with.
+    /// `CancelledError` maps to the non-standard 499 (client closed request).
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorCode::Other => 500,
+            ErrorCode::AuthenticationError => 401,
+            ErrorCode::ValidationError => 400,
+            ErrorCode::NotFoundError => 404,
+            ErrorCode::PermissionError => 403,
+            ErrorCode::TimeoutError => 504,
+            ErrorCode::NetworkError => 502,
+            ErrorCode::InvalidArgumentError => 400,
+            ErrorCode::IoError => 500,
+            ErrorCode::CancelledError => 499,
+            ErrorCode::UnimplementedError => 501,
+        }
+    }
+}
+
+static RETRY_IO_ERROR: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+static RETRY_OTHER: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+/// Sets whether `ErrorCode::IoError` or `ErrorCode::Other` is retryable for the
+/// whole process, e.g. when an application knows its I/O errors are transient.
+/// Returns false, changing nothing, for any other code, whose classification is
+/// fixed.
+pub fn set_retryable(code: ErrorCode, retryable: bool) -> bool {
+    let flag = match code {
+        ErrorCode::IoError => &RETRY_IO_ERROR,
+        ErrorCode::Other => &RETRY_OTHER,
+        _ => return false,
+    };
+    flag.store(retryable, core::sync::atomic::Ordering::Relaxed);
+    true
+}
+
+/// Returns whether `code` is retryable, like `ErrorCode::is_retryable`.
+#[unsafe(export_name = ffi_symbol!("error_code_is_retryable"))]
+pub extern "C" fn error_code_is_retryable(code: ErrorCode) -> bool {
+    code.is_retryable()
+}
+
+/// Returns the HTTP status for `code`, like `ErrorCode::http_status`.
+#[unsafe(export_name = ffi_symbol!("error_code_http_status"))]
+pub extern "C" fn error_code_http_status(code: ErrorCode) -> u16 {
+    code.http_status()
+}
+
+/// Maps an error type to the `ErrorCode` reported across the FFI boundary,
+/// so each error type can declare its own code in one place.
+///
+/// `error_code` defaults to `ErrorCode::Other`, so opting in only takes an
+/// empty `impl ErrorClassify for MyError {}`. A blanket impl for every `Error`
+/// would prevent types from overriding the code, as Rust has no specialization.
+pub trait ErrorClassify {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode::Other
+    }
+}
+
+#[cfg(feature = "std")]
+impl ErrorClassify for std::io::Error {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode::IoError
+    }
+}
+
+/// An error struct containing an error code, a description string, an
+/// optional machine-readable detail string and an optional Rust backtrace
+/// (both null when unused).
+///
+/// An aggregated error from `ExternResult::err_many` also owns the individual
+/// errors it combines, as an array of `errors_len` errors at `errors`; for any
+/// other error `errors` is null and `errors_len` is 0.
+///
+/// The `backtrace` field is always present so the C layout does not depend on
+/// crate features; it is only filled in with the `backtrace` feature, when
+/// `RUST_LIB_BACKTRACE` or `RUST_BACKTRACE` is set to anything other than `0`.
 /// #Safety
 ///
 /// Callers are responsible for managing the memory for the return value.
@@ -42,12 +211,393 @@ pub enum ErrorCode {
 pub struct ExternError {
     code: ErrorCode,
     message: *const c_char,
+    detail: *const c_char,
+    backtrace: *const c_char,
+    errors: *mut ExternError,
+    errors_len: usize,
+}
+
+impl ExternError {
+    /// Builds an error from a C-provided message, e.g. to re-wrap an error
+    /// that came back from C.
+    ///
+    /// The message is copied into a fresh allocation owned by the returned
+    /// error, so `msg` remains owned by the caller. A null `msg` is treated
+    /// as an empty message. The copy is released the same way as the message
+    /// of an error created by `ExternResult::err`.
+    ///
+    /// # Safety
+    ///
+    /// `msg` must be null or point to a valid NUL-terminated C string.
+    pub unsafe fn from_raw(code: ErrorCode, msg: *const c_char) -> Self {
+        let message = safe_cstr(msg).map_or_else(alloc::ffi::CString::default, CStr::to_owned);
+        ExternError {
+            code,
+            message: message.into_raw(),
+            detail: core::ptr::null(),
+            backtrace: capture_backtrace(),
+            errors: core::ptr::null_mut(),
+            errors_len: 0,
+        }
+    }
+
+    /// Builds an error whose message is capped at `max_len` bytes, e.g. for
+    /// stack dumps or messages from untrusted sources.
+    ///
+    /// A longer message is cut at the last char boundary that leaves room for a
+    /// `...` marker within `max_len`, so a code point is never split and the
+    /// marked message never exceeds `max_len`. Below 3 bytes only as many dots
+    /// as fit remain.
+    pub fn err_truncated<S>(code: ErrorCode, msg: S, max_len: usize) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut message = msg.into();
+        if message.len() > max_len {
+            const MARKER: &str = "...";
+            let end = (0..=max_len.saturating_sub(MARKER.len()))
+                .rev()
+                .find(|&i| message.is_char_boundary(i))
+                .unwrap_or(0);
+            message.truncate(end);
+            message.push_str(&MARKER[..max_len.min(MARKER.len())]);
+        }
+        ExternError {
+            code,
+            message: crate::string::string_to_c_char(message),
+            detail: core::ptr::null(),
+            backtrace: capture_backtrace(),
+            errors: core::ptr::null_mut(),
+            errors_len: 0,
+        }
+    }
+
+    /// The error code.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// The error message, or an empty string if it is null or not valid UTF-8.
+    pub fn message(&self) -> &str {
+        safe_cstr(self.message).map_or("", |message| message.to_str().unwrap_or(""))
+    }
+
+    /// The detail string, if one was set.
+    pub fn detail(&self) -> Option<&str> {
+        safe_cstr(self.detail).map(|detail| detail.to_str().unwrap_or(""))
+    }
+
+    /// The Rust backtrace captured when the error was created, if any.
+    pub fn backtrace(&self) -> Option<&str> {
+        safe_cstr(self.backtrace).map(|backtrace| backtrace.to_str().unwrap_or(""))
+    }
+
+    /// The individual errors combined into this one by `ExternResult::err_many`,
+    /// or an empty slice for any other error.
+    pub fn errors(&self) -> &[ExternError] {
+        if self.errors.is_null() {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(self.errors, self.errors_len) }
+    }
+}
+
+/// Captures the current backtrace as an owned C string if backtraces are
+/// enabled through the environment, or returns null.
+///
+/// The environment is read on every call rather than cached as
+/// `Backtrace::capture` does, so enabling it at runtime takes effect.
+#[cfg(feature = "backtrace")]
+fn capture_backtrace() -> *const c_char {
+    let enabled = std::env::var_os("RUST_LIB_BACKTRACE")
+        .or_else(|| std::env::var_os("RUST_BACKTRACE"))
+        .is_some_and(|value| value != "0");
+    if !enabled {
+        return core::ptr::null();
+    }
+    let backtrace = std::backtrace::Backtrace::force_capture();
+    crate::string::string_to_c_char(backtrace.to_string())
+}
+
+#[cfg(not(feature = "backtrace"))]
+fn capture_backtrace() -> *const c_char {
+    core::ptr::null()
+}
+
+/// Borrows the C string at `ptr`, or returns `None` if it is null.
+///
+/// Every read of an error's strings goes through here, so an `ExternError`
+/// handed back from C with a null message or detail never reaches `CStr::from_ptr`.
+/// Each string is read exactly once and never followed further, so formatting
+/// an error cannot recurse.
+fn safe_cstr<'a>(ptr: *const c_char) -> Option<&'a CStr> {
+    (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) })
+}
+
+/// Builds an `ExternError` with optional structured fields, such as a
+/// machine-readable detail code or a retry-after hint.
+#[derive(Debug)]
+pub struct ExternErrorBuilder {
+    code: ErrorCode,
+    message: String,
+    detail: Option<String>,
+}
+
+impl ExternErrorBuilder {
+    pub fn new(code: ErrorCode) -> Self {
+        ExternErrorBuilder {
+            code,
+            message: String::new(),
+            detail: None,
+        }
+    }
+
+    pub fn code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn message<S>(mut self, msg: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.message = msg.into();
+        self
+    }
+
+    pub fn detail<S>(mut self, detail: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Produces the error. Callers are responsible for managing its memory.
+    pub fn build(self) -> *mut ExternError {
+        #[cfg(feature = "log")]
+        log::log!(error_log_level(), "{:?}: {}", self.code, self.message);
+        Box::into_raw(Box::new(ExternError {
+            code: self.code,
+            message: crate::string::string_to_c_char(self.message),
+            detail: crate::string::opt_string_to_c_char(self.detail.as_deref()),
+            backtrace: capture_backtrace(),
+            errors: core::ptr::null_mut(),
+            errors_len: 0,
+        }))
+    }
+
+    /// Produces an error `ExternResult` holding the error.
+    pub fn into_result(self) -> *mut ExternResult {
+        ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: core::ptr::null_mut(),
+            err: self.build(),
+            warning: core::ptr::null(),
+            has_value: 0,
+        }
+        .into_boxed_raw()
+    }
+}
+
+#[cfg(feature = "log")]
+static ERROR_LOG_LEVEL: core::sync::atomic::AtomicUsize =
+    core::sync::atomic::AtomicUsize::new(log::Level::Error as usize);
+
+/// Sets the level at which error creation is logged. Defaults to `Error`.
+#[cfg(feature = "log")]
+pub fn set_error_log_level(level: log::Level) {
+    ERROR_LOG_LEVEL.store(level as usize, core::sync::atomic::Ordering::Relaxed);
+}
+
+#[cfg(feature = "log")]
+fn error_log_level() -> log::Level {
+    match ERROR_LOG_LEVEL.load(core::sync::atomic::Ordering::Relaxed) {
+        1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+/// Serializes an error as `{"code":"NetworkError","message":"..."}` for
+/// structured logging. The code is the `ErrorCode` variant name.
+/// Returns null if `err` is null.
+///
+/// The caller must free the returned string with `destroy_c_char`.
+#[cfg(feature = "serde")]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_to_json"))]
+pub extern "C" fn extern_error_to_json(err: *const ExternError) -> *mut c_char {
+    #[derive(serde::Serialize)]
+    struct JsonError<'a> {
+        code: ErrorCode,
+        message: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<&'a str>,
+    }
+
+    let Some(error) = (unsafe { err.as_ref() }) else {
+        return core::ptr::null_mut();
+    };
+    let json = serde_json::to_string(&JsonError {
+        code: error.code,
+        message: error.message(),
+        detail: error.detail(),
+    })
+    .unwrap_or_default();
+    crate::string::string_to_c_char(json)
+}
+
+/// Copies `err` into caller-owned storage, for hosts that cannot free Rust memory.
+///
+/// The code is written to `code_out` and the message to `msg_out`, truncated to fit
+/// `msg_cap` bytes including the terminating NUL; truncation may split a multi-byte
+/// UTF-8 character. Like `snprintf`, returns the full message length in bytes
+/// without the NUL, so the message was copied whole if the result is below `msg_cap`.
+/// Pass a null `msg_out` to only query the length. Null outputs are skipped, and a
+/// null `err` writes nothing and returns 0.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_copy"))]
+pub extern "C" fn extern_error_copy(
+    err: *const ExternError,
+    code_out: *mut i32,
+    msg_out: *mut c_char,
+    msg_cap: usize,
+) -> usize {
+    let Some(error) = (unsafe { err.as_ref() }) else {
+        return 0;
+    };
+    if !code_out.is_null() {
+        unsafe { *code_out = error.code as i32 };
+    }
+    let message = safe_cstr(error.message).map_or(&[][..], CStr::to_bytes);
+    if !msg_out.is_null() && msg_cap > 0 {
+        let copied = message.len().min(msg_cap - 1);
+        unsafe {
+            core::ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, msg_out, copied);
+            *msg_out.add(copied) = 0;
+        }
+    }
+    message.len()
+}
+
+/// Creates an independent copy of `err`, e.g. to both log and return an error.
+///
+/// The message, detail and any aggregated errors are copied into fresh
+/// allocations, so the copy and the original can each be freed with
+/// `free_extern_error` in any order. Returns null if `err` is null.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_clone"))]
+pub extern "C" fn extern_error_clone(err: *const ExternError) -> *mut ExternError {
+    match unsafe { err.as_ref() } {
+        Some(error) => Box::into_raw(Box::new(deep_copy(error))),
+        None => core::ptr::null_mut(),
+    }
+}
+
+/// Copies `error` and everything it owns into fresh allocations.
+fn deep_copy(error: &ExternError) -> ExternError {
+    let copy = |ptr: *const c_char| {
+        safe_cstr(ptr).map_or(core::ptr::null(), |s| {
+            s.to_owned().into_raw() as *const c_char
+        })
+    };
+    let errors =
+        crate::slice::ExternSlice::from_vec(error.errors().iter().map(deep_copy).collect());
+    ExternError {
+        code: error.code,
+        message: copy(error.message),
+        detail: copy(error.detail),
+        backtrace: copy(error.backtrace),
+        errors: errors.data,
+        errors_len: errors.len,
+    }
+}
+
+/// Returns the number of individual errors combined into `err` by
+/// `ExternResult::err_many`, or 0 if `err` is null or not an aggregate.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_errors_len"))]
+pub extern "C" fn extern_error_errors_len(err: *const ExternError) -> usize {
+    unsafe { err.as_ref() }.map_or(0, |error| error.errors().len())
+}
+
+/// Returns the aggregated error at `index`, or null if `err` is null or `index`
+/// is out of bounds. The error remains owned by `err` and must not be freed.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_get_error"))]
+pub extern "C" fn extern_error_get_error(
+    err: *const ExternError,
+    index: usize,
+) -> *const ExternError {
+    unsafe { err.as_ref() }
+        .and_then(|error| error.errors().get(index))
+        .map_or(core::ptr::null(), |error| error as *const ExternError)
+}
+
+/// Returns the backtrace captured when `err` was created, or null if `err` is
+/// null or none was captured. The string remains owned by the error.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_error_get_backtrace"))]
+pub extern "C" fn extern_error_get_backtrace(err: *const ExternError) -> *const c_char {
+    unsafe { err.as_ref() }.map_or(core::ptr::null(), |error| error.backtrace)
+}
+
+/// Releases an `ExternError` and the message, detail, backtrace and aggregated
+/// errors it owns. A null `err` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("free_extern_error"))]
+pub extern "C" fn free_extern_error(err: *mut ExternError) {
+    if err.is_null() {
+        return;
+    }
+    let error = unsafe { Box::from_raw(err) };
+    unsafe { release_contents(&error) };
+}
+
+/// Frees everything `error` owns, but not `error` itself.
+///
+/// # Safety
+///
+/// Every non-null pointer in `error` must be owned by it and not freed since.
+unsafe fn release_contents(error: &ExternError) {
+    for string in [error.message, error.detail, error.backtrace] {
+        if !string.is_null() {
+            let _ = unsafe { alloc::ffi::CString::from_raw(string as *mut c_char) };
+        }
+    }
+    if !error.errors.is_null() {
+        let errors = unsafe {
+            Box::from_raw(core::ptr::slice_from_raw_parts_mut(
+                error.errors,
+                error.errors_len,
+            ))
+        };
+        for error in errors.iter() {
+            unsafe { release_contents(error) };
+        }
+    }
 }
 
-/// A C representation of Rust's [Result](std::result::Result).
+/// The layout version of `ExternResult`, stored in its leading `version` field.
+/// It is bumped whenever a field is added or changed, so C code compiled against
+/// an older layout can detect the mismatch before reading any other field.
+///
+/// Version 1 is `{ version, ok, err, warning, has_value }`.
+pub const EXTERN_RESULT_ABI_VERSION: u32 = 1;
+
+/// A C representation of Rust's [Result](core::result::Result).
+/// The leading `version` is always `EXTERN_RESULT_ABI_VERSION` and stays first in
+/// every future layout; see `extern_result_abi_version`.
 /// A value of `Ok` results in `ok` containing a raw pointer as a `c_void`
 /// and `err` containing a null pointer.
 /// A value of `Err` results in `value` containing a null pointer and `err` containing an error struct.
+/// A successful result may also carry a non-fatal `warning` message, which is null unless set
+/// and always null for errors.
+/// `has_value` is 1 when a success carries a payload, even one whose pointer is null (see
+/// `ok_ptr`), and 0 for `ok_null` and errors, so C can tell "no value" from "null value".
 ///
 /// #Safety
 ///
@@ -57,27 +607,70 @@ pub struct ExternError {
 #[repr(C)]
 #[derive(Debug)]
 pub struct ExternResult {
+    pub version: u32,
     pub ok: *const c_void, // We could have used `*const T` instead, but that would have meant creating one `free` function per variant.
     pub err: *const ExternError,
+    pub warning: *const c_char,
+    pub has_value: u8,
+}
+
+/// The warning is owned by the result, so it is released together with it,
+/// e.g. by `extern_result_destroy`.
+impl Drop for ExternResult {
+    fn drop(&mut self) {
+        if !self.warning.is_null() {
+            let _ = unsafe { alloc::ffi::CString::from_raw(self.warning as *mut c_char) };
+        }
+    }
 }
 
 impl ExternResult {
+    /// Moves this result to the heap for C, e.g. one built with `From<Result>`.
+    /// Every constructor returning a `*mut ExternResult` allocates through here.
+    pub fn into_boxed_raw(self) -> *mut Self {
+        crate::memory::leak_check::register();
+        Box::into_raw(Box::new(self))
+    }
+
+    /// Creates a success result holding `result` boxed as a `Box<T>`.
+    ///
+    /// The value keeps the size and alignment of `T` only while it is freed as a
+    /// `Box<T>`, e.g. through `define_result_destructor!` or `extern_result_into_result`.
     pub fn ok<T>(result: T) -> *mut Self {
         Self::ok_ptr(Box::into_raw(Box::new(result)))
     }
 
     pub fn ok_ptr<T>(result: *mut T) -> *mut Self {
-        Box::into_raw(Box::new(ExternResult {
-            ok: result as *const _ as *const c_void,
-            err: std::ptr::null_mut(),
-        }))
+        ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: untagged_ok(result as *const c_void),
+            err: core::ptr::null_mut(),
+            warning: core::ptr::null(),
+            has_value: 1,
+        }
+        .into_boxed_raw()
     }
 
     pub fn ok_null() -> *mut Self {
-        Box::into_raw(Box::new(ExternResult {
-            ok: std::ptr::null_mut(),
-            err: std::ptr::null_mut(),
-        }))
+        ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: core::ptr::null_mut(),
+            err: core::ptr::null_mut(),
+            warning: core::ptr::null(),
+            has_value: 0,
+        }
+        .into_boxed_raw()
+    }
+
+    /// Creates a success result that also carries a non-fatal warning for the
+    /// caller to surface. The warning is freed together with the result.
+    pub fn ok_with_warning<T, S>(result: T, warning: S) -> *mut Self
+    where
+        S: Into<String>,
+    {
+        let ptr = Self::ok(result);
+        unsafe { (*ptr).warning = crate::string::string_to_c_char(warning) };
+        ptr
     }
 
     pub fn ok_optional<T>(result: &Option<T>) -> *mut Self {
@@ -87,550 +680,3051 @@ impl ExternResult {
         }
     }
 
+    /// Converts the result of a side-effecting operation, producing `ok_null()`
+    /// on success instead of boxing `()`, and an error with the code from
+    /// `default_error_code` carrying the error's message on failure.
+    pub fn from_unit_result<E>(result: Result<(), E>) -> *mut Self
+    where
+        E: core::error::Error,
+    {
+        match result {
+            Ok(()) => Self::ok_null(),
+            Err(e) => Self::err(default_error_code(), e.to_string()),
+        }
+    }
+
+    /// Creates an error result combining several errors, e.g. from a batch operation.
+    ///
+    /// The message joins the individual messages with newlines. The code is the
+    /// one all errors share, or `ErrorCode::Other` if they differ or `errors` is
+    /// empty. Each error stays recoverable with its own code and message through
+    /// `extern_error_errors_len` and `extern_error_get_error`.
+    pub fn err_many(errors: &[(ErrorCode, String)]) -> *mut Self {
+        let code = match errors.split_first() {
+            Some(((first, _), rest)) if rest.iter().all(|(code, _)| code == first) => *first,
+            _ => ErrorCode::Other,
+        };
+        let message = errors
+            .iter()
+            .map(|(_, message)| message.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let individual = errors
+            .iter()
+            .map(|(code, message)| ExternError {
+                code: *code,
+                message: crate::string::string_to_c_char(message.as_str()),
+                detail: core::ptr::null(),
+                backtrace: core::ptr::null(),
+                errors: core::ptr::null_mut(),
+                errors_len: 0,
+            })
+            .collect();
+        let individual = crate::slice::ExternSlice::from_vec(individual);
+        let result = Self::err(code, message);
+        unsafe {
+            let error = (*result).err as *mut ExternError;
+            (*error).errors = individual.data;
+            (*error).errors_len = individual.len;
+        }
+        result
+    }
+
+    /// Converts the result of a binary-producing operation, e.g. encryption or
+    /// compression. On success `ok` points to a boxed `ExternBuffer` holding the
+    /// bytes; on failure the result is an error with the code from
+    /// `default_error_code` carrying the error's message.
+    ///
+    /// Free either outcome with `extern_bytes_result_destroy`, which releases the
+    /// result, the boxed `ExternBuffer` and the bytes it points to, in that order.
+    pub fn from_bytes_result<E>(result: Result<Vec<u8>, E>) -> *mut Self
+    where
+        E: core::error::Error,
+    {
+        match result {
+            Ok(bytes) => Self::ok(ExternBuffer::from_vec(bytes)),
+            Err(e) => Self::err(default_error_code(), e.to_string()),
+        }
+    }
+
+    /// Creates an error result from an `anyhow::Error` with the code from
+    /// `default_error_code`. Its context chain is flattened into the message,
+    /// outermost first, e.g. `"Loading config: config.toml: not found"`.
+    #[cfg(feature = "anyhow")]
+    pub fn from_anyhow(e: anyhow::Error) -> *mut Self {
+        Self::err(default_error_code(), alloc::format!("{:#}", e))
+    }
+
+    /// Creates a `ValidationError` result for the input `field`, e.g. in form-style
+    /// validation. The field name is stored as the error's `detail` so C can read
+    /// it separately from `msg`.
+    pub fn validation_err(field: &str, msg: &str) -> *mut Self {
+        ExternErrorBuilder::new(ErrorCode::ValidationError)
+            .message(msg)
+            .detail(field)
+            .into_result()
+    }
+
+    /// Creates an error result from `e`, using `ErrorClassify` to pick its code.
+    pub fn err_from<E>(e: E) -> *mut Self
+    where
+        E: core::error::Error + ErrorClassify,
+    {
+        Self::err(e.error_code(), e.to_string())
+    }
+
     pub fn err<S>(code: ErrorCode, msg: S) -> *mut Self
     where
         S: Into<String>,
     {
-        Box::into_raw(Box::new(ExternResult {
-            ok: std::ptr::null_mut(),
-            err: Box::into_raw(Box::new(ExternError {
-                code,
-                message: crate::string::string_to_c_char(msg),
-            })),
-        }))
+        ExternErrorBuilder::new(code).message(msg).into_result()
+    }
+
+    /// Wraps an error the caller already owns, e.g. one returned by a sub-call,
+    /// in an error result without copying its message.
+    ///
+    /// Ownership of `err` moves into the result: free it only through the result,
+    /// e.g. with `extern_result_destroy`, and never with `free_extern_error`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `err` is null.
+    pub fn from_error_ptr(err: *mut ExternError) -> *mut Self {
+        assert_pointer_not_null!(err);
+        ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: core::ptr::null_mut(),
+            err,
+            warning: core::ptr::null(),
+            has_value: 0,
+        }
+        .into_boxed_raw()
+    }
+
+    /// Packages an `ok` pointer and an error produced elsewhere, e.g. by C code,
+    /// into a result; the inverse of `extern_result_get_ok` and
+    /// `extern_result_get_err`.
+    ///
+    /// A non-null `err` makes an error result and moves into it, as with
+    /// `from_error_ptr`. Otherwise the result is a success holding `ok`, which
+    /// counts as carrying a value only if it is non-null.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both `ok` and `err` are non-null.
+    pub fn from_parts(ok: *mut c_void, err: *mut ExternError) -> *mut Self {
+        assert!(
+            ok.is_null() || err.is_null(),
+            "ExternResult cannot hold both an ok value and an error"
+        );
+        if !err.is_null() {
+            return Self::from_error_ptr(err);
+        }
+        if ok.is_null() {
+            return Self::ok_null();
+        }
+        Self::ok_ptr(ok)
+    }
+
+    /// Creates a success result whose `ok` value is `value` serialized as a JSON
+    /// C string. A serialization failure yields an `ErrorCode::Other` error.
+    ///
+    /// The caller must free the `ok` string with `destroy_c_char`, not by
+    /// treating it as a boxed value.
+    #[cfg(feature = "serde")]
+    pub fn ok_json<T: serde::Serialize>(value: &T) -> *mut Self {
+        match serde_json::to_string(value) {
+            Ok(json) => Self::ok_ptr(crate::string::string_to_c_char(json)),
+            Err(e) => Self::err(ErrorCode::Other, e.to_string()),
+        }
+    }
+
+    /// Like `ok`, but in debug builds records the type of `result` so that
+    /// `ok_downcast` can verify it. Release builds behave exactly like `ok`.
+    pub fn ok_tagged<T: 'static>(result: T) -> *mut Self {
+        let ptr = Self::ok(result);
+        #[cfg(all(debug_assertions, feature = "std"))]
+        TAGGING_USED.store(true, core::sync::atomic::Ordering::Release);
+        #[cfg(all(debug_assertions, feature = "std"))]
+        ok_type_tags()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                unsafe { (*ptr).ok } as usize,
+                (core::any::TypeId::of::<T>(), core::any::type_name::<T>()),
+            );
+        ptr
+    }
+
+    /// Reads the `ok` value of `ptr` as a `T`, or returns `None` if it is null.
+    ///
+    /// In debug builds, if the value was created with `ok_tagged`, its recorded
+    /// type is checked first and a mismatch panics instead of reading the value
+    /// as the wrong type. Values that were not tagged are not checked. A tag is
+    /// forgotten when its value is freed or taken out through this crate, and
+    /// every constructor clears a stale tag left at the address it reuses.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid `ExternResult` whose `ok` value, if any, is a `T`
+    /// that outlives `'a`. In release builds neither the type nor the alignment of
+    /// the value is checked.
+    pub unsafe fn ok_downcast<'a, T: 'static>(ptr: *const Self) -> Option<&'a T> {
+        let ok = unsafe { ptr.as_ref() }?.ok;
+        if ok.is_null() {
+            return None;
+        }
+        assert_aligned!(ok, T);
+        // The tag is copied out so that a mismatch panics without holding the lock
+        #[cfg(all(debug_assertions, feature = "std"))]
+        let tag = TAGGING_USED
+            .load(core::sync::atomic::Ordering::Acquire)
+            .then(|| {
+                ok_type_tags()
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .get(&(ok as usize))
+                    .copied()
+            })
+            .flatten();
+        #[cfg(all(debug_assertions, feature = "std"))]
+        if let Some((type_id, type_name)) = tag {
+            assert!(
+                type_id == core::any::TypeId::of::<T>(),
+                "ExternResult ok value is a `{}`, not a `{}`",
+                type_name,
+                core::any::type_name::<T>()
+            );
+        }
+        Some(unsafe { &*(ok as *const T) })
+    }
+
+    /// Reborrows the `ok` value of `result` as a `&mut T`, e.g. to call a mutating
+    /// method on an opaque object held by C. Returns `None` if `result` or its
+    /// `ok` is null.
+    ///
+    /// # Safety
+    ///
+    /// The `ok` value must be a `T` that outlives `'a`, and the returned reference
+    /// must be the only access to it while it is alive: no other reference may be
+    /// created from the same result, here or on the C side, until it is dropped.
+    pub unsafe fn ok_as_mut<'a, T>(result: *mut Self) -> Option<&'a mut T> {
+        let ok = unsafe { result.as_ref() }?.ok as *mut T;
+        assert_aligned!(ok, T);
+        unsafe { ok.as_mut() }
+    }
+
+    // `Box::into_raw` never returns null, so the constructors above can be
+    // wrapped in `NonNull` without a check.
+    fn nonnull(ptr: *mut Self) -> NonNull<Self> {
+        unsafe { NonNull::new_unchecked(ptr) }
+    }
+
+    /// Like `ok`, but returns a `NonNull` so the type system records that the
+    /// result is valid. Use `into_raw` to obtain the C pointer at the boundary.
+    pub fn ok_nonnull<T>(result: T) -> NonNull<Self> {
+        Self::nonnull(Self::ok(result))
+    }
+
+    /// Like `ok_ptr`, but returns a `NonNull`.
+    pub fn ok_ptr_nonnull<T>(result: *mut T) -> NonNull<Self> {
+        Self::nonnull(Self::ok_ptr(result))
+    }
+
+    /// Like `ok_null`, but returns a `NonNull`.
+    pub fn ok_null_nonnull() -> NonNull<Self> {
+        Self::nonnull(Self::ok_null())
+    }
+
+    /// Like `err`, but returns a `NonNull`.
+    pub fn err_nonnull<S>(code: ErrorCode, msg: S) -> NonNull<Self>
+    where
+        S: Into<String>,
+    {
+        Self::nonnull(Self::err(code, msg))
+    }
+
+    /// Converts a `NonNull` result back into the raw pointer handed to C.
+    pub fn into_raw(result: NonNull<Self>) -> *mut Self {
+        result.as_ptr()
+    }
+}
+
+/// Side table of `ok` addresses to the type recorded by `ExternResult::ok_tagged`.
+#[cfg(all(debug_assertions, feature = "std"))]
+type OkTypeTags =
+    std::sync::Mutex<std::collections::HashMap<usize, (core::any::TypeId, &'static str)>>;
+
+/// Set the first time `ExternResult::ok_tagged` runs, so that programs which
+/// never tag a value never touch the side table or its lock.
+#[cfg(all(debug_assertions, feature = "std"))]
+static TAGGING_USED: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+#[cfg(all(debug_assertions, feature = "std"))]
+fn ok_type_tags() -> &'static OkTypeTags {
+    static TAGS: std::sync::OnceLock<OkTypeTags> = std::sync::OnceLock::new();
+    TAGS.get_or_init(Default::default)
+}
+
+/// Forgets the type recorded by `ExternResult::ok_tagged` for `ok`, e.g. once the
+/// value is freed or handed out, so a later value at the same address is not
+/// checked against it. Returns `ok` for use in a struct literal; every path that
+/// puts a new value into `ok` goes through here.
+pub(crate) fn untagged_ok(ok: *const c_void) -> *const c_void {
+    #[cfg(all(debug_assertions, feature = "std"))]
+    if TAGGING_USED.load(core::sync::atomic::Ordering::Acquire) {
+        ok_type_tags()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(ok as usize));
     }
+    ok
+}
+
+static DEFAULT_ERROR_CODE: core::sync::atomic::AtomicI32 =
+    core::sync::atomic::AtomicI32::new(ErrorCode::Other as i32);
+
+/// Sets the code given to errors converted by `From<Result<T, E>> for ExternResult`,
+/// e.g. `ErrorCode::IoError` for an application whose errors are mostly I/O.
+/// Defaults to `ErrorCode::Other`. The setting is process-wide.
+///
+/// The other conversions of arbitrary errors use it too: `from_unit_result`,
+/// `from_bytes_result`, `from_anyhow`, `future::block_on_to_result` and the
+/// `From<Result>` of scalar results from `define_scalar_result!`. Errors
+/// that pick their own code, e.g. through `ErrorClassify` or `err`, ignore it.
+pub fn set_default_error_code(code: ErrorCode) {
+    DEFAULT_ERROR_CODE.store(code as i32, core::sync::atomic::Ordering::Relaxed);
+}
+
+/// The code set with `set_default_error_code`.
+pub fn default_error_code() -> ErrorCode {
+    ErrorCode::try_from(DEFAULT_ERROR_CODE.load(core::sync::atomic::Ordering::Relaxed))
+        .unwrap_or(ErrorCode::Other)
 }
 
+/// An error becomes an error result with the code from `default_error_code`,
+/// `ErrorCode::Other` unless changed, carrying the error's message.
 impl<T, E> From<Result<T, E>> for ExternResult
 where
-    E: std::error::Error,
+    E: core::error::Error,
 {
     fn from(result: Result<T, E>) -> Self {
         match result {
             Ok(value) => ExternResult {
-                ok: Box::into_raw(Box::new(value)) as *const _ as *const c_void,
-                err: std::ptr::null(),
+                version: EXTERN_RESULT_ABI_VERSION,
+                ok: untagged_ok(Box::into_raw(Box::new(value)) as *const c_void),
+                err: core::ptr::null(),
+                warning: core::ptr::null(),
+                has_value: 1,
             },
             Err(e) => ExternResult {
-                ok: std::ptr::null(),
-                err: Box::into_raw(Box::new(ExternError {
-                    code: ErrorCode::Other,
-                    message: crate::string::string_to_c_char(e.to_string()),
-                })),
+                version: EXTERN_RESULT_ABI_VERSION,
+                ok: core::ptr::null(),
+                err: ExternErrorBuilder::new(default_error_code())
+                    .message(e.to_string())
+                    .build(),
+                warning: core::ptr::null(),
+                has_value: 0,
             },
         }
-    }
-}
+    }
+}
+
+/// Releases an `ExternResult` and its warning, but not its `ok` value or error.
+#[cfg(not(all(feature = "debug-guards", debug_assertions)))]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_destroy"))]
+pub extern "C" fn extern_result_destroy(obj: *mut ExternResult) {
+    crate::memory::leak_check::unregister();
+    let _ = unsafe { Box::from_raw(obj) };
+}
+
+/// With `debug-guards`, the result is dropped and poisoned with null fields but
+/// its allocation is quarantined, so a second call is ignored rather than
+/// corrupting the heap.
+#[cfg(all(feature = "debug-guards", debug_assertions))]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_destroy"))]
+pub extern "C" fn extern_result_destroy(obj: *mut ExternResult) {
+    if crate::memory::guard::quarantine(obj as *const c_void, "ExternResult") {
+        crate::memory::leak_check::unregister();
+        unsafe {
+            core::ptr::drop_in_place(obj);
+            core::ptr::write(
+                obj,
+                ExternResult {
+                    version: EXTERN_RESULT_ABI_VERSION,
+                    ok: core::ptr::null(),
+                    err: core::ptr::null(),
+                    warning: core::ptr::null(),
+                    has_value: 0,
+                },
+            );
+        }
+    }
+}
+
+/// Returns whether `ptr` is a successful result. A null `ptr` is not.
+/// Prefer this and the other accessors over reading the fields directly so
+/// C code does not depend on the layout of `ExternResult`.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_is_ok"))]
+pub extern "C" fn extern_result_is_ok(ptr: *const ExternResult) -> bool {
+    unsafe { ptr.as_ref() }.is_some_and(|result| result.err.is_null())
+}
+
+/// Returns the `ExternResult` layout version this library produces, i.e.
+/// `EXTERN_RESULT_ABI_VERSION`, so C can check at startup that it was compiled
+/// against the same layout.
+#[unsafe(export_name = ffi_symbol!("extern_result_abi_version"))]
+pub extern "C" fn extern_result_abi_version() -> u32 {
+    EXTERN_RESULT_ABI_VERSION
+}
+
+define_c_enum!(
+    @name_fn [unsafe(export_name = ffi_symbol!("extern_result_tag_name"))]
+    /// The kind of an `ExternResult`, for bindings that prefer switching on a tag
+    /// to comparing pointers. See `extern_result_tag`.
+    pub enum ExternResultTag {
+        /// A success carrying a value, which may itself be a null pointer
+        Ok = 0,
+        /// A success without a value, e.g. from `ExternResult::ok_null`
+        OkNull = 1,
+        /// An error, whose details `extern_result_get_err` returns
+        Err = 2,
+    }
+);
+
+/// Returns the kind of `ptr`, computed from its fields. A null `ptr` is reported
+/// as `Err`, as `extern_result_is_ok` reports it unsuccessful.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_tag"))]
+pub extern "C" fn extern_result_tag(ptr: *const ExternResult) -> ExternResultTag {
+    match unsafe { ptr.as_ref() } {
+        Some(result) if result.err.is_null() && result.has_value != 0 => ExternResultTag::Ok,
+        Some(result) if result.err.is_null() => ExternResultTag::OkNull,
+        _ => ExternResultTag::Err,
+    }
+}
+
+/// Returns whether `ptr` is a success carrying a value, which may itself be a
+/// null pointer. A null `ptr`, an `ok_null` result and an error have none.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_has_value"))]
+pub extern "C" fn extern_result_has_value(ptr: *const ExternResult) -> bool {
+    unsafe { ptr.as_ref() }.is_some_and(|result| result.has_value != 0)
+}
+
+/// Returns the `ok` value of `ptr`, or null if `ptr` is null, an error, or
+/// a success without a value.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_get_ok"))]
+pub extern "C" fn extern_result_get_ok(ptr: *const ExternResult) -> *const c_void {
+    unsafe { ptr.as_ref() }.map_or(core::ptr::null(), |result| result.ok)
+}
+
+/// Returns the warning of `ptr`, or null if `ptr` is null or carries no warning.
+/// The warning remains owned by the result.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_get_warning"))]
+pub extern "C" fn extern_result_get_warning(ptr: *const ExternResult) -> *const c_char {
+    unsafe { ptr.as_ref() }.map_or(core::ptr::null(), |result| result.warning)
+}
+
+/// Returns the error of `ptr`, or null if `ptr` is null or successful.
+/// The error remains owned by the result.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_result_get_err"))]
+pub extern "C" fn extern_result_get_err(ptr: *const ExternResult) -> *const ExternError {
+    unsafe { ptr.as_ref() }.map_or(core::ptr::null(), |result| result.err)
+}
+
+/// A Rust-owned copy of an `ExternError`, produced when converting an
+/// `ExternResult` back into a Rust `Result`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedExternError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+impl core::fmt::Display for OwnedExternError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl core::error::Error for OwnedExternError {}
+
+impl From<&ExternError> for OwnedExternError {
+    fn from(error: &ExternError) -> Self {
+        OwnedExternError {
+            code: error.code,
+            message: error.message().to_owned(),
+            detail: error.detail().map(str::to_owned),
+        }
+    }
+}
+
+/// A borrowed look at an `ExternResult` that neither consumes nor frees it.
+#[derive(Debug, Clone, Copy)]
+pub struct ResultView<'a> {
+    /// Whether the result is a success.
+    pub is_ok: bool,
+    /// The error, if the result is an error.
+    pub err: Option<&'a ExternError>,
+}
+
+/// Inspects `ptr` without taking ownership, e.g. to check a result repeatedly in
+/// tests or wrappers before deciding who frees it. A null `ptr` is viewed as
+/// neither ok nor an error, i.e. `is_ok` is false and `err` is `None`.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid `ExternResult`, which must not be freed
+/// or mutated for as long as the returned view, i.e. `'a`, is in use.
+pub unsafe fn extern_result_peek<'a>(ptr: *const ExternResult) -> ResultView<'a> {
+    match unsafe { ptr.as_ref() } {
+        Some(result) => {
+            let err = unsafe { result.err.as_ref() };
+            ResultView {
+                is_ok: err.is_none(),
+                err,
+            }
+        }
+        None => ResultView {
+            is_ok: false,
+            err: None,
+        },
+    }
+}
+
+/// Consumes an `ExternResult`, e.g. one returned by C, and converts it back into a
+/// Rust `Result`. The `ExternResult` itself and its error are always freed.
+///
+/// An `ok` value is returned as `Some(Box<T>)` taking ownership of the pointer,
+/// and a null `ok` (see `ExternResult::ok_null`) as `None`.
+///
+/// # Safety
+///
+/// `ptr` must be a non-null pointer to an `ExternResult` that is not freed or
+/// owned anywhere else. If it holds an `ok` value, that value must have been
+/// allocated as a `Box<T>`; passing the wrong `T` is undefined behaviour.
+pub unsafe fn extern_result_into_result<T>(
+    ptr: *mut ExternResult,
+) -> Result<Option<Box<T>>, OwnedExternError> {
+    let owned = unsafe { OwnedExternResult::from_raw(ptr) };
+    match owned.error() {
+        Some(error) => Err(OwnedExternError::from(error)),
+        None => {
+            let ok = untagged_ok(owned.as_ref().ok) as *mut T;
+            Ok((!ok.is_null()).then(|| unsafe { Box::from_raw(ok) }))
+        }
+    }
+}
+
+/// Consumes an `ExternResult` carrying no value, e.g. from `ExternResult::ok_null`,
+/// and converts it into a Rust `Result<(), _>`. The result and its error are freed.
+///
+/// A null `ptr` becomes an `InvalidArgumentError`. A non-null `ok` value is not
+/// freed; use `extern_result_into_result` for results that carry one.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to an `ExternResult` that is not freed or owned
+/// anywhere else.
+pub unsafe fn extern_result_into_unit(ptr: *mut ExternResult) -> Result<(), OwnedExternError> {
+    if ptr.is_null() {
+        return Err(OwnedExternError {
+            code: ErrorCode::InvalidArgumentError,
+            message: String::from("ExternResult is null"),
+            detail: None,
+        });
+    }
+    unsafe { OwnedExternResult::from_raw(ptr) }.try_into()
+}
+
+/// Takes the `ok` value out of `ptr`, leaving the result without one, so a later
+/// destructor that frees `ok`, such as one from `define_result_destructor!`, does
+/// not free it a second time. Returns null if `ptr` is null or has no value.
+///
+/// The caller owns the returned value and must free it as its original type.
+///
+/// # Safety
+///
+/// A non-null `ptr` must point to a valid `ExternResult` that is not accessed
+/// elsewhere during the call.
+pub unsafe fn extern_result_take_ok(ptr: *mut ExternResult) -> *mut c_void {
+    let Some(result) = (unsafe { ptr.as_mut() }) else {
+        return core::ptr::null_mut();
+    };
+    result.has_value = 0;
+    untagged_ok(core::mem::replace(&mut result.ok, core::ptr::null())) as *mut c_void
+}
+
+/// Collects `iter` into a success result, stopping at the first error, e.g. when
+/// converting a stream of records where any one may fail.
+///
+/// On success `ok` points to a boxed `ExternSlice<T>` holding every item in order.
+/// The first error becomes an error result whose code comes from `ErrorClassify`,
+/// and no further items are pulled from `iter`.
+///
+/// Free the result with `extern_result_into_result::<ExternSlice<T>>`, then the
+/// slice with a destructor from `define_slice_destructor!`.
+pub fn collect_to_result<I, T, E>(iter: I) -> *mut ExternResult
+where
+    I: Iterator<Item = Result<T, E>>,
+    E: core::error::Error + ErrorClassify,
+{
+    match iter.collect::<Result<Vec<T>, E>>() {
+        Ok(items) => ExternResult::ok(crate::slice::ExternSlice::from_vec(items)),
+        Err(e) => ExternResult::err_from(e),
+    }
+}
+
+/// Combines the results of several sub-calls into one, succeeding only if all of
+/// them did, and frees every input either way so none of them can leak.
+///
+/// Returns `ok_null()` if every result is a success, otherwise a copy of the first
+/// error. A null entry counts as an `ErrorCode::InvalidArgumentError`. The inputs'
+/// `ok` values are untyped and could not be freed, so only results without a
+/// value are accepted, such as those from `ok_null` or `from_unit_result`.
+///
+/// If any input carries a value, an `ErrorCode::InvalidArgumentError` result is
+/// returned before anything is freed, and every input still belongs to the caller.
+///
+/// # Safety
+///
+/// Each non-null entry must be a result produced by this crate that is not freed
+/// or owned anywhere else; none of them may be used afterwards.
+pub unsafe fn combine_results(results: &[*mut ExternResult]) -> *mut ExternResult {
+    if results
+        .iter()
+        .any(|&ptr| unsafe { ptr.as_ref() }.is_some_and(|result| result.has_value != 0))
+    {
+        return ExternResult::err(
+            ErrorCode::InvalidArgumentError,
+            "combine_results cannot free ok values; pass results without a value",
+        );
+    }
+    let mut combined = None;
+    for &ptr in results {
+        if ptr.is_null() {
+            combined.get_or_insert_with(|| {
+                ExternResult::err(ErrorCode::InvalidArgumentError, "ExternResult is null")
+            });
+            continue;
+        }
+        let owned = unsafe { OwnedExternResult::from_raw(ptr) };
+        if let Some(error) = owned.error() {
+            combined.get_or_insert_with(|| ExternResult::from_error_ptr(extern_error_clone(error)));
+        }
+    }
+    combined.unwrap_or_else(ExternResult::ok_null)
+}
+
+/// Consumes an `ExternResult` by passing its `ok` value to `on_ok` or its error to
+/// `on_err`, then freeing the result and its error before returning what the
+/// callback returned. This replaces the inspect-then-free sequence, which leaks
+/// when the free is forgotten on one of the branches.
+///
+/// The `ok` value itself is not freed: `on_ok` receives ownership of it, e.g. to
+/// rebuild a `Box`, and may be called with a null pointer (see `ok_null`).
+/// The error is only borrowed for the duration of `on_err`.
+///
+/// # Safety
+///
+/// `ptr` must be a non-null pointer to an `ExternResult` that is not freed or
+/// owned anywhere else.
+pub unsafe fn with_extern_result<R>(
+    ptr: *mut ExternResult,
+    on_ok: impl FnOnce(*const c_void) -> R,
+    on_err: impl FnOnce(&ExternError) -> R,
+) -> R {
+    let owned = unsafe { OwnedExternResult::from_raw(ptr) };
+    match owned.error() {
+        Some(error) => on_err(error),
+        None => on_ok(untagged_ok(owned.as_ref().ok)),
+    }
+}
+
+/// Writes a success result holding `value` into caller-provided storage instead of
+/// allocating the `ExternResult` itself, e.g. a struct on the C caller's stack.
+///
+/// Only the outer struct lives in `out`; `value` is still boxed on the heap and
+/// must be freed by the caller with its own destructor. Never pass `out` to
+/// `extern_result_destroy`.
+///
+/// # Safety
+///
+/// `out` must be non-null and valid for writes. Any previous contents are
+/// overwritten without being freed.
+pub unsafe fn extern_result_init_ok<T>(out: *mut ExternResult, value: T) {
+    assert_pointer_not_null!(out);
+    unsafe {
+        out.write(ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: untagged_ok(Box::into_raw(Box::new(value)) as *const c_void),
+            err: core::ptr::null(),
+            warning: core::ptr::null(),
+            has_value: 1,
+        })
+    };
+}
+
+/// Writes an error result into caller-provided storage, like `extern_result_init_ok`.
+///
+/// The error is still heap-allocated and must be freed with `free_extern_error`.
+///
+/// # Safety
+///
+/// `out` must be non-null and valid for writes. Any previous contents are
+/// overwritten without being freed.
+pub unsafe fn extern_result_init_err<S>(out: *mut ExternResult, code: ErrorCode, msg: S)
+where
+    S: Into<String>,
+{
+    assert_pointer_not_null!(out);
+    unsafe {
+        out.write(ExternResult {
+            version: EXTERN_RESULT_ABI_VERSION,
+            ok: core::ptr::null(),
+            err: ExternErrorBuilder::new(code).message(msg).build(),
+            warning: core::ptr::null(),
+            has_value: 0,
+        })
+    };
+}
+
+/// Lets Rust callers write `let () = result.try_into()?;` for results that carry
+/// no value. The orphan rule rules out implementing this for `*mut ExternResult`
+/// directly; see `extern_result_into_unit` for raw pointers.
+impl TryFrom<OwnedExternResult> for () {
+    type Error = OwnedExternError;
+
+    fn try_from(result: OwnedExternResult) -> Result<(), OwnedExternError> {
+        result
+            .error()
+            .map_or(Ok(()), |error| Err(OwnedExternError::from(error)))
+    }
+}
+
+/// Frees a result from `ExternResult::from_bytes_result`: the result and its
+/// error, the boxed `ExternBuffer` in `ok` and the bytes it owns.
+/// A null `result` is ignored.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("extern_bytes_result_destroy"))]
+pub extern "C" fn extern_bytes_result_destroy(result: *mut ExternResult) {
+    if !result.is_null()
+        && let Ok(Some(buf)) = unsafe { extern_result_into_result::<ExternBuffer>(result) }
+    {
+        crate::buffer::extern_buffer_destroy(*buf);
+    }
+}
+
+/// Creates a function with a given `$name` that frees an `ExternResult` whose
+/// `ok` value is a `$t`, together with that value, its error and warning.
+/// A null result is ignored.
+///
+/// The value is released as the exact `Box<$t>` it was allocated as, so its size
+/// and alignment are preserved. Prefer this over freeing `ok` with the untyped
+/// `destroy`, which loses both and is wrong for anything but a single byte,
+/// most visibly for over-aligned types such as `#[repr(align(64))]` structs.
+#[macro_export]
+macro_rules! define_result_destructor (
+    ($name:ident, $t:ty) => (
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $name(result: *mut $crate::result::ExternResult) {
+            if !result.is_null() {
+                let _ = unsafe { $crate::result::extern_result_into_result::<$t>(result) };
+            }
+        }
+    )
+);
+
+/// Creates a `#[repr(C)]` result type `$name` that carries a scalar `$t` inline
+/// instead of boxing it behind `ExternResult::ok`.
+///
+/// On success `is_err` is false, `err` is null and `value` holds the result.
+/// On failure `is_err` is true, `value` is `Default::default()` and `err` must
+/// be released with `free_extern_error`. `From<Result<$t, E>>` gives errors the
+/// code from `default_error_code`.
+#[macro_export]
+macro_rules! define_scalar_result (
+    ($(#[$meta:meta])* $name:ident, $t:ty) => (
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Debug)]
+        pub struct $name {
+            pub value: $t,
+            pub is_err: bool,
+            pub err: *const $crate::result::ExternError,
+        }
+
+        impl $name {
+            pub fn ok(value: $t) -> Self {
+                $name {
+                    value,
+                    is_err: false,
+                    err: ::core::ptr::null(),
+                }
+            }
+
+            pub fn err<S>(code: $crate::result::ErrorCode, msg: S) -> Self
+            where
+                S: Into<$crate::__alloc::string::String>,
+            {
+                $name {
+                    value: <$t as ::core::default::Default>::default(),
+                    is_err: true,
+                    err: $crate::result::ExternErrorBuilder::new(code).message(msg).build(),
+                }
+            }
+        }
+
+        impl<E> From<Result<$t, E>> for $name
+        where
+            E: ::core::error::Error,
+        {
+            fn from(result: Result<$t, E>) -> Self {
+                match result {
+                    Ok(value) => Self::ok(value),
+                    Err(e) => Self::err(
+                        $crate::result::default_error_code(),
+                        $crate::__alloc::string::ToString::to_string(&e),
+                    ),
+                }
+            }
+        }
+    )
+);
+
+define_scalar_result!(
+    /// A scalar result carrying an `f32`. NaN and infinities are valid `ok` values.
+    ExternResultF32,
+    f32
+);
+define_scalar_result!(
+    /// A scalar result carrying an `f64`. NaN and infinities are valid `ok` values.
+    ExternResultF64,
+    f64
+);
+define_scalar_result!(
+    /// A scalar result carrying an `i128`.
+    ///
+    /// `i128` is 16-byte aligned on x86_64 and aarch64, matching `__int128`, so the
+    /// struct is 16-byte aligned and `value` is followed by padding. C code must use
+    /// `__int128` (or an `_Alignas(16)` pair of 64-bit halves) for the field.
+    ExternResultI128,
+    i128
+);
+define_scalar_result!(
+    /// A scalar result carrying a `u128`, with the same alignment caveats as
+    /// `ExternResultI128`; use `unsigned __int128` in C.
+    ExternResultU128,
+    u128
+);
+
+/// An owning handle to a `*mut ExternResult` for Rust-side callers.
+///
+/// Dropping the handle frees the result, its error and the error message.
+/// The `ok` value is untyped and is *not* freed; callers that own it must
+/// release it themselves. Use `into_raw()` to hand the result on to C instead.
+#[derive(Debug)]
+pub struct OwnedExternResult {
+    ptr: *mut ExternResult,
+}
+
+impl OwnedExternResult {
+    /// Takes ownership of `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a non-null pointer produced by this crate that is not
+    /// freed or owned anywhere else.
+    pub unsafe fn from_raw(ptr: *mut ExternResult) -> Self {
+        assert_pointer_not_null!(ptr);
+        OwnedExternResult { ptr }
+    }
+
+    /// Releases ownership and returns the raw pointer, e.g. to return it to C.
+    pub fn into_raw(self) -> *mut ExternResult {
+        let ptr = self.ptr;
+        core::mem::forget(self);
+        ptr
+    }
+
+    /// The error, if this result is an error.
+    pub fn error(&self) -> Option<&ExternError> {
+        unsafe { self.as_ref().err.as_ref() }
+    }
+
+    /// Whether this result is an error.
+    pub fn is_err(&self) -> bool {
+        !self.as_ref().err.is_null()
+    }
+}
+
+impl AsRef<ExternResult> for OwnedExternResult {
+    fn as_ref(&self) -> &ExternResult {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl Drop for OwnedExternResult {
+    fn drop(&mut self) {
+        crate::memory::leak_check::unregister();
+        let result = unsafe { Box::from_raw(self.ptr) };
+        free_extern_error(result.err as *mut ExternError);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    // Helper error type for testing
+    #[derive(Debug)]
+    struct TestError {
+        message: String,
+    }
+
+    impl std::fmt::Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}", self.message)
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    impl ErrorClassify for TestError {}
+
+    #[test]
+    fn test_extern_result_ok_basic() {
+        let value = 42i32;
+        let result_ptr = ExternResult::ok(value);
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(!result.ok.is_null());
+            assert!(result.err.is_null());
+
+            // Clean up - free inner value first, then the result
+            let _ = Box::from_raw(result.ok as *mut i32);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_ok_ptr() {
+        let value = Box::new(100u64);
+        let value_ptr = Box::into_raw(value);
+        let result_ptr = ExternResult::ok_ptr(value_ptr);
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(!result.ok.is_null());
+            assert!(result.err.is_null());
+            assert_eq!(result.ok as *const u64, value_ptr as *const u64);
+
+            // Clean up - free inner value first, then the result
+            let _ = Box::from_raw(value_ptr);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_ok_null() {
+        let result_ptr = ExternResult::ok_null();
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(result.ok.is_null());
+            assert!(result.err.is_null());
+
+            // Clean up
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_ok_optional_some() {
+        let value = Some(42i32);
+        let result_ptr = ExternResult::ok_optional(&value);
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(!result.ok.is_null());
+            assert!(result.err.is_null());
+
+            // Clean up - need to free the value inside
+            let _ = Box::from_raw(result.ok as *mut i32);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_ok_optional_none() {
+        let value: Option<i32> = None;
+        let result_ptr = ExternResult::ok_optional(&value);
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(result.ok.is_null());
+            assert!(result.err.is_null());
+
+            // Clean up
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_err() {
+        let result_ptr = ExternResult::err(ErrorCode::AuthenticationError, "Auth failed");
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(result.ok.is_null());
+            assert!(!result.err.is_null());
+
+            let error = &*result.err;
+            assert!(!error.message.is_null());
+
+            // Verify error message
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            let message = c_str.to_str().unwrap();
+            assert_eq!(message, "Auth failed");
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_err_with_string() {
+        let error_msg = String::from("Error message");
+        let result_ptr = ExternResult::err(ErrorCode::Other, error_msg.clone());
+
+        unsafe {
+            assert!(!result_ptr.is_null());
+            let result = &*result_ptr;
+            assert!(result.ok.is_null());
+            assert!(!result.err.is_null());
+
+            let error = &*result.err;
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            let message = c_str.to_str().unwrap();
+            assert_eq!(message, error_msg);
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_from_result_ok() {
+        let rust_result: Result<i32, TestError> = Ok(123);
+        let extern_result = ExternResult::from(rust_result);
+
+        assert!(!extern_result.ok.is_null());
+        assert!(extern_result.err.is_null());
+
+        unsafe {
+            let value = *(extern_result.ok as *const i32);
+            assert_eq!(value, 123);
+
+            // Clean up
+            let _ = Box::from_raw(extern_result.ok as *mut i32);
+        }
+    }
+
+    #[test]
+    fn test_from_result_err() {
+        let rust_result: Result<i32, TestError> = Err(TestError {
+            message: String::from("Test error"),
+        });
+        let extern_result = ExternResult::from(rust_result);
+
+        assert!(extern_result.ok.is_null());
+        assert!(!extern_result.err.is_null());
+
+        unsafe {
+            let error = &*extern_result.err;
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            let message = c_str.to_str().unwrap();
+            assert_eq!(message, "Test error");
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(extern_result.err as *mut ExternError);
+        }
+    }
+
+    #[test]
+    fn test_error_code_variants() {
+        // Test both error code variants
+        let auth_err = ExternResult::err(ErrorCode::AuthenticationError, "Auth error");
+        let other_err = ExternResult::err(ErrorCode::Other, "Other error");
+
+        unsafe {
+            let auth_error = &*(&*auth_err).err;
+            let other_error = &*(&*other_err).err;
+
+            // Verify we can distinguish error codes
+            match auth_error.code {
+                ErrorCode::AuthenticationError => {}
+                _ => panic!("Expected AuthenticationError"),
+            }
+
+            match other_error.code {
+                ErrorCode::Other => {}
+                _ => panic!("Expected Other error"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw(auth_error.message as *mut _);
+            let _ = Box::from_raw((&*auth_err).err as *mut ExternError);
+            let _ = Box::from_raw(auth_err);
+
+            let _ = CString::from_raw(other_error.message as *mut _);
+            let _ = Box::from_raw((&*other_err).err as *mut ExternError);
+            let _ = Box::from_raw(other_err);
+        }
+    }
+
+    #[test]
+    fn test_extern_result_destroy() {
+        // Test that the destructor doesn't crash with a valid pointer
+        let result_ptr = ExternResult::ok(42i32);
+
+        unsafe {
+            // First free the inner value
+            let result = &*result_ptr;
+            let _ = Box::from_raw(result.ok as *mut i32);
+        }
+
+        // Now destroy the ExternResult itself
+        extern_result_destroy(result_ptr);
+    }
+
+    #[test]
+    #[cfg(all(feature = "debug-guards", debug_assertions))]
+    fn test_extern_result_double_destroy_detected() {
+        let result_ptr = ExternResult::ok_with_warning(1u8, "stale");
+        unsafe {
+            let _ = Box::from_raw((*result_ptr).ok as *mut u8);
+        }
+
+        extern_result_destroy(result_ptr);
+        // Without the guard this would free the result and its warning again
+        extern_result_destroy(result_ptr);
+
+        assert!(crate::memory::guard::is_quarantined(
+            result_ptr as *const c_void
+        ));
+    }
+
+    #[test]
+    fn test_multiple_extern_results() {
+        // Create multiple results to ensure no memory conflicts
+        let results: Vec<*mut ExternResult> = (0..10).map(ExternResult::ok).collect();
+
+        unsafe {
+            for result_ptr in results {
+                let result = &*result_ptr;
+                assert!(!result.ok.is_null());
+                assert!(result.err.is_null());
+
+                // Clean up
+                let _ = Box::from_raw(result.ok as *mut i32);
+                let _ = Box::from_raw(result_ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extern_result_with_complex_type() {
+        #[derive(Debug, PartialEq)]
+        struct ComplexType {
+            id: u64,
+            name: String,
+            values: Vec<i32>,
+        }
+
+        let complex = ComplexType {
+            id: 123,
+            name: String::from("Test"),
+            values: vec![1, 2, 3, 4, 5],
+        };
+
+        let result_ptr = ExternResult::ok(complex);
+
+        unsafe {
+            let result = &*result_ptr;
+            assert!(!result.ok.is_null());
+
+            let value = &*(result.ok as *const ComplexType);
+            assert_eq!(value.id, 123);
+            assert_eq!(value.name, "Test");
+            assert_eq!(value.values, vec![1, 2, 3, 4, 5]);
+
+            // Clean up
+            let _ = Box::from_raw(result.ok as *mut ComplexType);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_extern_error_unicode_message() {
+        let result_ptr = ExternResult::err(ErrorCode::Other, "Error: 错误 🚨");
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            let message = c_str.to_str().unwrap();
+            assert_eq!(message, "Error: 错误 🚨");
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_all_error_code_variants() {
+        // Test all error code variants to ensure they can be created and used
+        let test_cases = vec![
+            (ErrorCode::Other, "Generic error"),
+            (ErrorCode::AuthenticationError, "Auth failed"),
+            (ErrorCode::ValidationError, "Invalid input"),
+            (ErrorCode::NotFoundError, "Resource not found"),
+            (ErrorCode::PermissionError, "Access denied"),
+            (ErrorCode::TimeoutError, "Request timed out"),
+            (ErrorCode::NetworkError, "Network unavailable"),
+            (ErrorCode::InvalidArgumentError, "Bad argument"),
+            (ErrorCode::IoError, "File read failed"),
+            (ErrorCode::CancelledError, "Operation cancelled"),
+            (ErrorCode::UnimplementedError, "Not implemented yet"),
+        ];
+
+        for (code, message) in test_cases {
+            let result_ptr = ExternResult::err(code, message);
+
+            unsafe {
+                assert!(!result_ptr.is_null());
+                let result = &*result_ptr;
+                assert!(result.ok.is_null());
+                assert!(!result.err.is_null());
+
+                let error = &*result.err;
+                let c_str = std::ffi::CStr::from_ptr(error.message);
+                let msg_str = c_str.to_str().unwrap();
+                assert_eq!(msg_str, message);
+
+                // Clean up
+                let _ = CString::from_raw(error.message as *mut _);
+                let _ = Box::from_raw(result.err as *mut ExternError);
+                let _ = Box::from_raw(result_ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validation_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::ValidationError,
+            "Email format is invalid"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::ValidationError => {}
+                _ => panic!("Expected ValidationError"),
+            }
+
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            assert_eq!(c_str.to_str().unwrap(), "Email format is invalid");
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn test_from_anyhow_flattens_context() {
+        use anyhow::Context;
+
+        let error = Err::<(), _>(TestError {
+            message: String::from("config.toml not found"),
+        })
+        .context("Reading settings")
+        .context("Starting service")
+        .unwrap_err();
+
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::from_anyhow(error)) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert_eq!(
+            error.message(),
+            "Starting service: Reading settings: config.toml not found"
+        );
+    }
+
+    #[test]
+    fn test_validation_err_with_field() {
+        let result_ptr = ExternResult::validation_err("email", "Email format is invalid");
+
+        let owned = unsafe { OwnedExternResult::from_raw(result_ptr) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::ValidationError);
+        assert_eq!(error.message(), "Email format is invalid");
+        assert_eq!(error.detail(), Some("email"));
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(error.detail) }, c"email");
+    }
+
+    #[test]
+    fn test_not_found_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::NotFoundError,
+            "User with ID 123 not found"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::NotFoundError => {}
+                _ => panic!("Expected NotFoundError"),
+            }
+
+            let c_str = std::ffi::CStr::from_ptr(error.message);
+            assert_eq!(c_str.to_str().unwrap(), "User with ID 123 not found");
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_permission_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::PermissionError,
+            "Insufficient permissions to access resource"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::PermissionError => {}
+                _ => panic!("Expected PermissionError"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw((&*result.err).message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_timeout_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::TimeoutError,
+            "Operation exceeded 30 second timeout"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::TimeoutError => {}
+                _ => panic!("Expected TimeoutError"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_network_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::NetworkError,
+            "Failed to connect to server: connection refused"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::NetworkError => {}
+                _ => panic!("Expected NetworkError"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_invalid_argument_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::InvalidArgumentError,
+            "Argument 'count' must be positive"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::InvalidArgumentError => {}
+                _ => panic!("Expected InvalidArgumentError"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_io_error() {
+        let result_ptr = ExternResult::err(
+            ErrorCode::IoError,
+            "Failed to read file: permission denied"
+        );
+
+        unsafe {
+            let result = &*result_ptr;
+            let error = &*result.err;
+
+            match error.code {
+                ErrorCode::IoError => {}
+                _ => panic!("Expected IoError"),
+            }
+
+            // Clean up
+            let _ = CString::from_raw(error.message as *mut _);
+            let _ = Box::from_raw(result.err as *mut ExternError);
+            let _ = Box::from_raw(result_ptr);
+        }
+    }
+
+    #[test]
+    fn test_owned_extern_result_err() {
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err(ErrorCode::NotFoundError, "Missing"))
+        };
+
+        assert!(owned.is_err());
+        assert!(owned.as_ref().ok.is_null());
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::NotFoundError);
+        assert_eq!(error.message(), "Missing");
+
+        // Result, error and message are all freed here
+    }
+
+    #[test]
+    fn test_owned_extern_result_ok() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::ok(7u32)) };
+
+        assert!(!owned.is_err());
+        assert!(owned.error().is_none());
+
+        // The ok value is not owned by the guard
+        let _ = unsafe { Box::from_raw(owned.as_ref().ok as *mut u32) };
+    }
+
+    #[test]
+    fn test_owned_extern_result_into_raw() {
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err(ErrorCode::Other, "Handed to C"))
+        };
+        let raw = owned.into_raw();
+
+        // Ownership went back to the raw pointer, so re-wrap it to clean up
+        unsafe {
+            assert_eq!((*(*raw).err).message(), "Handed to C");
+            drop(OwnedExternResult::from_raw(raw));
+        }
+    }
+
+    #[test]
+    fn test_owned_extern_result_drops_in_loop() {
+        for i in 0..100 {
+            let owned = unsafe {
+                OwnedExternResult::from_raw(ExternResult::err(
+                    ErrorCode::IoError,
+                    format!("Error {}", i),
+                ))
+            };
+            assert_eq!(owned.error().unwrap().message(), format!("Error {}", i));
+        }
+    }
+
+    #[test]
+    fn test_extern_error_from_raw() {
+        let original = CString::new("From C").unwrap();
+        let error = unsafe { ExternError::from_raw(ErrorCode::NetworkError, original.as_ptr()) };
+
+        // The message is an independent copy
+        assert_ne!(error.message, original.as_ptr());
+        drop(original);
+
+        assert_eq!(error.code(), ErrorCode::NetworkError);
+        assert_eq!(error.message(), "From C");
+
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_from_raw_null() {
+        let error = unsafe { ExternError::from_raw(ErrorCode::Other, std::ptr::null()) };
+
+        assert!(!error.message.is_null());
+        assert_eq!(error.message(), "");
+
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_truncated_short_message() {
+        let error = ExternError::err_truncated(ErrorCode::Other, "Short", 5);
+
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert_eq!(error.message(), "Short");
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_truncated_on_char_boundary() {
+        // Each character is 3 bytes, so the 7 bytes left before the marker end
+        // inside the third one
+        let error = ExternError::err_truncated(ErrorCode::IoError, "世界世界世界", 10);
+
+        assert_eq!(error.message(), "世界...");
+        free_extern_error(Box::into_raw(Box::new(error)));
+
+        let error = ExternError::err_truncated(ErrorCode::IoError, "🦀 crab", 2);
+        assert_eq!(error.message(), "..");
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_truncated_fits_max_len() {
+        let error = ExternError::err_truncated(ErrorCode::Other, "0123456789", 8);
+
+        assert_eq!(error.message(), "01234...");
+        assert_eq!(error.message().len(), 8);
+        free_extern_error(Box::into_raw(Box::new(error)));
+
+        for max_len in 0..12 {
+            let error = ExternError::err_truncated(ErrorCode::Other, "0123456789", max_len);
+            assert_eq!(error.message().len(), max_len.min(10));
+            free_extern_error(Box::into_raw(Box::new(error)));
+        }
+    }
+
+    #[test]
+    fn test_extern_error_readers_null_strings() {
+        let error = ExternError {
+            code: ErrorCode::IoError,
+            message: std::ptr::null(),
+            detail: std::ptr::null(),
+            backtrace: std::ptr::null(),
+            errors: std::ptr::null_mut(),
+            errors_len: 0,
+        };
+
+        assert_eq!(error.message(), "");
+        assert_eq!(error.detail(), None);
+
+        let owned = OwnedExternError::from(&error);
+        assert_eq!(owned.message, "");
+        assert_eq!(owned.detail, None);
+        assert_eq!(owned.to_string(), "IoError: ");
+
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_copy_exact_fit() {
+        let message = CString::new("Timed out").unwrap();
+        let error = unsafe { ExternError::from_raw(ErrorCode::TimeoutError, message.as_ptr()) };
+        let mut code = -1;
+        let mut buf = [0x7f as c_char; 10];
+
+        let len = extern_error_copy(&error, &mut code, buf.as_mut_ptr(), buf.len());
+
+        assert_eq!(len, 9);
+        assert_eq!(code, ErrorCode::TimeoutError as i32);
+        assert_eq!(
+            unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) },
+            message.as_c_str()
+        );
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_copy_truncates() {
+        let message = CString::new("Connection refused").unwrap();
+        let error = unsafe { ExternError::from_raw(ErrorCode::NetworkError, message.as_ptr()) };
+        let mut buf = [0x7f as c_char; 8];
+
+        let len = extern_error_copy(&error, std::ptr::null_mut(), buf.as_mut_ptr(), 5);
+
+        assert_eq!(len, 18);
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) }, c"Conn");
+        // Nothing is written past `msg_cap`
+        assert_eq!(buf[5], 0x7f);
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_copy_query_length() {
+        let message = CString::new("Not found").unwrap();
+        let error = unsafe { ExternError::from_raw(ErrorCode::NotFoundError, message.as_ptr()) };
+        let mut code = -1;
+
+        let len = extern_error_copy(&error, &mut code, std::ptr::null_mut(), 64);
+
+        assert_eq!(len, 9);
+        assert_eq!(code, ErrorCode::NotFoundError as i32);
+        free_extern_error(Box::into_raw(Box::new(error)));
+    }
+
+    #[test]
+    fn test_extern_error_copy_null_error() {
+        let mut code = -1;
+        let mut buf = [0x7f as c_char; 4];
+
+        let len = extern_error_copy(std::ptr::null(), &mut code, buf.as_mut_ptr(), buf.len());
+
+        assert_eq!(len, 0);
+        assert_eq!(code, -1);
+        assert_eq!(buf[0], 0x7f);
+    }
+
+    #[test]
+    fn test_extern_error_clone_outlives_original() {
+        let original = ExternErrorBuilder::new(ErrorCode::ValidationError)
+            .message("Invalid email")
+            .detail("email")
+            .build();
+
+        let clone = extern_error_clone(original);
+        free_extern_error(original);
+
+        let clone_ref = unsafe { &*clone };
+        assert_eq!(clone_ref.code(), ErrorCode::ValidationError);
+        assert_eq!(clone_ref.message(), "Invalid email");
+        assert_eq!(clone_ref.detail(), Some("email"));
+        free_extern_error(clone);
+    }
+
+    #[cfg(not(feature = "backtrace"))]
+    #[test]
+    fn test_backtrace_null_without_feature() {
+        let error = ExternErrorBuilder::new(ErrorCode::Other).build();
+
+        assert_eq!(unsafe { &*error }.backtrace(), None);
+        assert!(extern_error_get_backtrace(error).is_null());
+        free_extern_error(error);
+    }
+
+    #[test]
+    fn test_extern_result_take_ok() {
+        let result = ExternResult::ok(String::from("taken"));
+
+        let ok = unsafe { extern_result_take_ok(result) };
+        assert!(!ok.is_null());
+        assert!(extern_result_get_ok(result).is_null());
+        assert!(!extern_result_has_value(result));
+        assert!(extern_result_is_ok(result));
+        // Taking again yields nothing
+        assert!(unsafe { extern_result_take_ok(result) }.is_null());
+
+        // Freeing the result with its value no longer touches the taken value
+        let remaining = unsafe { extern_result_into_result::<String>(result) };
+        assert!(remaining.unwrap().is_none());
+
+        let value = unsafe { Box::from_raw(ok as *mut String) };
+        assert_eq!(*value, "taken");
+    }
+
+    #[test]
+    fn test_extern_result_take_ok_null_and_err() {
+        assert!(unsafe { extern_result_take_ok(std::ptr::null_mut()) }.is_null());
+
+        let result = ExternResult::err(ErrorCode::Other, "Failure");
+        assert!(unsafe { extern_result_take_ok(result) }.is_null());
+        assert!(!extern_result_get_err(result).is_null());
+        drop(unsafe { OwnedExternResult::from_raw(result) });
+    }
+
+    define_slice_destructor!(destroy_collected_u32_slice, u32);
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_collect_to_result_all_ok() {
+        let items = (1..=4u32).map(Ok::<_, std::io::Error>);
+
+        let result = collect_to_result(items);
+
+        let slice = unsafe { extern_result_into_result::<crate::slice::ExternSlice<u32>>(result) }
+            .unwrap()
+            .unwrap();
+        assert_eq!(slice.len, 4);
+        assert_eq!(
+            unsafe { std::slice::from_raw_parts(slice.data, slice.len) },
+            [1, 2, 3, 4]
+        );
+        destroy_collected_u32_slice(*slice);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_collect_to_result_short_circuits() {
+        let mut pulled = 0;
+        let items = (1..=5u32).inspect(|_| pulled += 1).map(|i| {
+            if i == 3 {
+                Err(std::io::Error::other("Record 3 is corrupt"))
+            } else {
+                Ok(i)
+            }
+        });
+
+        let result = collect_to_result(items);
+
+        let owned = unsafe { OwnedExternResult::from_raw(result) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::IoError);
+        assert_eq!(error.message(), "Record 3 is corrupt");
+        assert!(owned.as_ref().ok.is_null());
+        assert_eq!(pulled, 3);
+    }
+
+    #[test]
+    fn test_combine_results_all_ok() {
+        let results = [
+            ExternResult::ok_null(),
+            ExternResult::from_unit_result(Ok::<(), TestError>(())),
+            ExternResult::ok_null(),
+        ];
+
+        let combined = unsafe { combine_results(&results) };
+
+        assert!(extern_result_is_ok(combined));
+        assert!(!extern_result_has_value(combined));
+        extern_result_destroy(combined);
+    }
+
+    #[test]
+    fn test_combine_results_returns_first_error() {
+        let results = [
+            ExternResult::ok_null(),
+            ExternResult::err(ErrorCode::TimeoutError, "Shard 2 timed out"),
+            ExternResult::err(ErrorCode::NetworkError, "Shard 3 unreachable"),
+        ];
+        let first_error = extern_result_get_err(results[1]);
+
+        let combined = unsafe { combine_results(&results) };
+
+        let owned = unsafe { OwnedExternResult::from_raw(combined) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::TimeoutError);
+        assert_eq!(error.message(), "Shard 2 timed out");
+        // A copy, since the input and its error have been freed
+        assert_ne!(error as *const ExternError, first_error);
+    }
+
+    #[test]
+    fn test_combine_results_null_and_empty() {
+        let results = [ExternResult::ok_null(), std::ptr::null_mut()];
+
+        let combined = unsafe { combine_results(&results) };
+        let owned = unsafe { OwnedExternResult::from_raw(combined) };
+        assert_eq!(
+            owned.error().unwrap().code(),
+            ErrorCode::InvalidArgumentError
+        );
+
+        let combined = unsafe { combine_results(&[]) };
+        assert!(extern_result_is_ok(combined));
+        extern_result_destroy(combined);
+    }
+
+    #[test]
+    fn test_combine_results_rejects_values() {
+        let results = [ExternResult::ok_null(), ExternResult::ok(1u32)];
+
+        let combined = unsafe { combine_results(&results) };
+
+        let owned = unsafe { OwnedExternResult::from_raw(combined) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::InvalidArgumentError);
+        assert!(error.message().contains("cannot free ok values"));
+        // Nothing was freed, so the inputs still belong to the caller
+        extern_result_destroy(results[0]);
+        let _ = unsafe { extern_result_into_result::<u32>(results[1]) };
+    }
+
+    #[test]
+    fn test_from_parts_ok_only() {
+        let value = Box::into_raw(Box::new(7u64)) as *mut c_void;
+
+        let result = ExternResult::from_parts(value, std::ptr::null_mut());
+
+        assert!(extern_result_is_ok(result));
+        assert!(extern_result_has_value(result));
+        assert_eq!(extern_result_get_ok(result), value as *const c_void);
+        let value = unsafe { extern_result_into_result::<u64>(result) };
+        assert_eq!(*value.unwrap().unwrap(), 7);
+
+        let result = ExternResult::from_parts(std::ptr::null_mut(), std::ptr::null_mut());
+        assert!(extern_result_is_ok(result));
+        assert!(!extern_result_has_value(result));
+        extern_result_destroy(result);
+    }
+
+    #[test]
+    fn test_from_parts_err_only() {
+        let err = ExternErrorBuilder::new(ErrorCode::PermissionError)
+            .message("Read-only volume")
+            .build();
+
+        let result = ExternResult::from_parts(std::ptr::null_mut(), err);
+
+        assert!(!extern_result_is_ok(result));
+        assert_eq!(extern_result_get_err(result), err as *const ExternError);
+        let error = unsafe { extern_result_into_result::<u8>(result) }.unwrap_err();
+        assert_eq!(error.code, ErrorCode::PermissionError);
+        assert_eq!(error.message, "Read-only volume");
+    }
+
+    #[test]
+    #[should_panic(expected = "both an ok value and an error")]
+    fn test_from_parts_rejects_both() {
+        let mut value = 0u8;
+        let err = ExternErrorBuilder::new(ErrorCode::Other).build();
+        ExternResult::from_parts(&mut value as *mut u8 as *mut c_void, err);
+    }
+
+    #[test]
+    fn test_from_error_ptr_moves_error() {
+        let err = ExternErrorBuilder::new(ErrorCode::NetworkError)
+            .message("Upstream unreachable")
+            .detail("db-1")
+            .build();
+
+        let result = ExternResult::from_error_ptr(err);
+
+        assert!(!extern_result_is_ok(result));
+        // The very same error is reused, not a copy
+        assert_eq!(extern_result_get_err(result), err as *const ExternError);
+        let error = unsafe { &*extern_result_get_err(result) };
+        assert_eq!(error.code(), ErrorCode::NetworkError);
+        assert_eq!(error.message(), "Upstream unreachable");
+
+        extern_result_destroy(result);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected null pointer")]
+    fn test_from_error_ptr_null_panics() {
+        ExternResult::from_error_ptr(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_err_many_keeps_each_error() {
+        let result = ExternResult::err_many(&[
+            (ErrorCode::ValidationError, String::from("Row 1: no id")),
+            (ErrorCode::NotFoundError, String::from("Row 2: no user")),
+            (ErrorCode::ValidationError, String::from("Row 3: bad email")),
+        ]);
+        let error = extern_result_get_err(result);
+
+        let aggregate = unsafe { &*error };
+        assert_eq!(aggregate.code(), ErrorCode::Other);
+        assert_eq!(
+            aggregate.message(),
+            "Row 1: no id\nRow 2: no user\nRow 3: bad email"
+        );
+
+        assert_eq!(extern_error_errors_len(error), 3);
+        let individual: Vec<_> = (0..3)
+            .map(|i| unsafe { &*extern_error_get_error(error, i) })
+            .map(|error| (error.code(), error.message()))
+            .collect();
+        assert_eq!(
+            individual,
+            [
+                (ErrorCode::ValidationError, "Row 1: no id"),
+                (ErrorCode::NotFoundError, "Row 2: no user"),
+                (ErrorCode::ValidationError, "Row 3: bad email"),
+            ]
+        );
+        assert!(extern_error_get_error(error, 3).is_null());
+
+        drop(unsafe { OwnedExternResult::from_raw(result) });
+    }
+
+    #[test]
+    fn test_err_many_shared_code() {
+        let result = ExternResult::err_many(&[
+            (ErrorCode::TimeoutError, String::from("Shard 1 timed out")),
+            (ErrorCode::TimeoutError, String::from("Shard 2 timed out")),
+        ]);
+        let owned = unsafe { OwnedExternResult::from_raw(result) };
+        let error = owned.error().unwrap();
+
+        assert_eq!(error.code(), ErrorCode::TimeoutError);
+
+        // A clone owns copies of the individual errors
+        let clone = extern_error_clone(error);
+        let clone_errors = unsafe { &*clone }.errors();
+        assert_eq!(clone_errors.len(), 2);
+        assert_eq!(clone_errors[1].message(), "Shard 2 timed out");
+        free_extern_error(clone);
+    }
+
+    #[test]
+    fn test_err_many_empty_and_plain_errors() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::err_many(&[])) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert!(error.errors().is_empty());
+
+        let plain = ExternErrorBuilder::new(ErrorCode::IoError).build();
+        assert_eq!(extern_error_errors_len(plain), 0);
+        assert!(extern_error_get_error(plain, 0).is_null());
+        assert_eq!(extern_error_errors_len(std::ptr::null()), 0);
+        free_extern_error(plain);
+    }
+
+    #[test]
+    fn test_extern_error_clone_null() {
+        assert!(extern_error_clone(std::ptr::null()).is_null());
+    }
+
+    #[test]
+    fn test_extern_result_peek_ok() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::ok_null()) };
+
+        for _ in 0..3 {
+            let view = unsafe { extern_result_peek(owned.as_ref()) };
+            assert!(view.is_ok);
+            assert!(view.err.is_none());
+        }
+    }
+
+    #[test]
+    fn test_extern_result_peek_err() {
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err(ErrorCode::TimeoutError, "Timed out"))
+        };
+
+        for _ in 0..3 {
+            let view = unsafe { extern_result_peek(owned.as_ref()) };
+            assert!(!view.is_ok);
+            let error = view.err.unwrap();
+            assert_eq!(error.code(), ErrorCode::TimeoutError);
+            assert_eq!(error.message(), "Timed out");
+        }
+        // Peeking left the result intact for its owner to free
+        assert!(owned.is_err());
+    }
+
+    #[test]
+    fn test_extern_result_peek_null() {
+        let view = unsafe { extern_result_peek(std::ptr::null()) };
+
+        assert!(!view.is_ok);
+        assert!(view.err.is_none());
+    }
+
+    #[test]
+    fn test_with_extern_result_ok() {
+        let value = unsafe {
+            with_extern_result(
+                ExternResult::ok(String::from("payload")),
+                |ok| *Box::from_raw(ok as *mut String),
+                |_| panic!("Expected the ok branch"),
+            )
+        };
+
+        assert_eq!(value, "payload");
+    }
+
+    #[test]
+    fn test_with_extern_result_err() {
+        let result = ExternErrorBuilder::new(ErrorCode::NotFoundError)
+            .message("No such key")
+            .detail("key")
+            .into_result();
+
+        let seen = unsafe {
+            with_extern_result(
+                result,
+                |_| panic!("Expected the err branch"),
+                |error| {
+                    (
+                        error.code(),
+                        error.message().to_owned(),
+                        error.detail().map(str::to_owned),
+                    )
+                },
+            )
+        };
+
+        assert_eq!(
+            seen,
+            (
+                ErrorCode::NotFoundError,
+                String::from("No such key"),
+                Some(String::from("key"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_with_extern_result_ok_null() {
+        let was_null =
+            unsafe { with_extern_result(ExternResult::ok_null(), |ok| ok.is_null(), |_| false) };
+
+        assert!(was_null);
+    }
+
+    #[test]
+    fn test_extern_result_into_result_ok() {
+        let result =
+            unsafe { extern_result_into_result::<String>(ExternResult::ok(String::from("value"))) };
+
+        assert_eq!(
+            result.unwrap().as_deref().map(String::as_str),
+            Some("value")
+        );
+    }
+
+    #[test]
+    fn test_extern_result_into_result_ok_null() {
+        let result = unsafe { extern_result_into_result::<u64>(ExternResult::ok_null()) };
+
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_extern_result_into_unit_ok() {
+        let result = unsafe { extern_result_into_unit(ExternResult::ok_null()) };
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_extern_result_into_unit_err() {
+        let result = unsafe {
+            extern_result_into_unit(ExternResult::err(ErrorCode::PermissionError, "Read-only"))
+        };
+
+        let error = result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::PermissionError);
+        assert_eq!(error.message, "Read-only");
+    }
+
+    #[test]
+    fn test_extern_result_into_unit_null() {
+        let result = unsafe { extern_result_into_unit(std::ptr::null_mut()) };
+
+        assert_eq!(result.unwrap_err().code, ErrorCode::InvalidArgumentError);
+    }
+
+    #[test]
+    fn test_extern_result_init_ok_on_stack() {
+        let mut storage = std::mem::MaybeUninit::<ExternResult>::uninit();
+
+        unsafe { extern_result_init_ok(storage.as_mut_ptr(), String::from("in place")) };
+        let result = unsafe { storage.assume_init() };
+
+        assert!(extern_result_is_ok(&result));
+        assert!(extern_result_has_value(&result));
+        assert!(result.warning.is_null());
+        let value = unsafe { Box::from_raw(result.ok as *mut String) };
+        assert_eq!(*value, "in place");
+    }
+
+    #[test]
+    fn test_extern_result_init_err_on_stack() {
+        let mut storage = std::mem::MaybeUninit::<ExternResult>::uninit();
+
+        unsafe { extern_result_init_err(storage.as_mut_ptr(), ErrorCode::IoError, "Disk full") };
+        let result = unsafe { storage.assume_init() };
+
+        assert!(!extern_result_is_ok(&result));
+        assert!(result.ok.is_null());
+        assert_eq!(result.has_value, 0);
+        let error = unsafe { &*result.err };
+        assert_eq!(error.code(), ErrorCode::IoError);
+        assert_eq!(error.message(), "Disk full");
+        free_extern_error(result.err as *mut ExternError);
+    }
+
+    #[test]
+    fn test_owned_extern_result_try_into_unit() {
+        fn call(ptr: *mut ExternResult) -> Result<(), OwnedExternError> {
+            let () = unsafe { OwnedExternResult::from_raw(ptr) }.try_into()?;
+            Ok(())
+        }
 
-define_destructor!(extern_result_destroy, ExternResult);
+        assert!(call(ExternResult::ok_null()).is_ok());
+        assert_eq!(
+            call(ExternResult::err(ErrorCode::TimeoutError, "Too slow"))
+                .unwrap_err()
+                .to_string(),
+            "TimeoutError: Too slow"
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::ffi::CString;
+    #[test]
+    fn test_extern_result_into_result_err() {
+        let result = unsafe {
+            extern_result_into_result::<u64>(ExternResult::err(ErrorCode::TimeoutError, "Too slow"))
+        };
 
-    // Helper error type for testing
-    #[derive(Debug)]
-    struct TestError {
-        message: String,
+        let error = result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::TimeoutError);
+        assert_eq!(error.message, "Too slow");
+        assert_eq!(error.to_string(), "TimeoutError: Too slow");
     }
 
-    impl std::fmt::Display for TestError {
-        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            write!(f, "{}", self.message)
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_extern_error_to_json() {
+        let message = CString::new("Connection \"refused\"\n").unwrap();
+        let error = unsafe { ExternError::from_raw(ErrorCode::NetworkError, message.as_ptr()) };
+
+        let json = extern_error_to_json(&error);
+        assert_eq!(
+            crate::string::c_char_to_string(json),
+            r#"{"code":"NetworkError","message":"Connection \"refused\"\n"}"#
+        );
+
+        unsafe {
+            let _ = CString::from_raw(json);
+            free_extern_error(Box::into_raw(Box::new(error)));
         }
     }
 
-    impl std::error::Error for TestError {}
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_extern_error_to_json_null() {
+        assert!(extern_error_to_json(std::ptr::null()).is_null());
+    }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_extern_result_ok_basic() {
-        let value = 42i32;
-        let result_ptr = ExternResult::ok(value);
+    fn test_extern_error_to_json_null_strings() {
+        let error = ExternError {
+            code: ErrorCode::Other,
+            message: std::ptr::null(),
+            detail: std::ptr::null(),
+            backtrace: std::ptr::null(),
+            errors: std::ptr::null_mut(),
+            errors_len: 0,
+        };
 
-        unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(!result.ok.is_null());
-            assert!(result.err.is_null());
+        let json = extern_error_to_json(&error);
+        assert_eq!(
+            crate::string::c_char_to_string(json),
+            r#"{"code":"Other","message":""}"#
+        );
 
-            // Clean up - free inner value first, then the result
-            let _ = Box::from_raw(result.ok as *mut i32);
-            let _ = Box::from_raw(result_ptr);
+        unsafe {
+            let _ = CString::from_raw(json);
         }
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_extern_result_ok_ptr() {
-        let value = Box::new(100u64);
-        let value_ptr = Box::into_raw(value);
-        let result_ptr = ExternResult::ok_ptr(value_ptr);
+    fn test_extern_result_ok_json() {
+        #[derive(serde::Serialize)]
+        struct Point {
+            x: i32,
+            label: String,
+        }
 
-        unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(!result.ok.is_null());
-            assert!(result.err.is_null());
-            assert_eq!(result.ok as *const u64, value_ptr as *const u64);
+        let result = ExternResult::ok_json(&Point {
+            x: 3,
+            label: "origin".to_string(),
+        });
 
-            // Clean up - free inner value first, then the result
-            let _ = Box::from_raw(value_ptr);
-            let _ = Box::from_raw(result_ptr);
+        unsafe {
+            assert!((*result).err.is_null());
+            let json = (*result).ok as *mut c_char;
+            let parsed: serde_json::Value =
+                serde_json::from_str(crate::string::c_char_to_string(json)).unwrap();
+            assert_eq!(parsed, serde_json::json!({ "x": 3, "label": "origin" }));
+
+            crate::memory::destroy_c_char(json);
+            let _ = Box::from_raw(result);
         }
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_extern_result_ok_null() {
-        let result_ptr = ExternResult::ok_null();
+    fn test_extern_result_ok_json_failure() {
+        // JSON object keys must be strings
+        let value = std::collections::HashMap::from([(vec![1u8], 1u8)]);
+        let result = ExternResult::ok_json(&value);
 
         unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(result.ok.is_null());
-            assert!(result.err.is_null());
+            assert!((*result).ok.is_null());
+            let err = &*(*result).err;
+            assert_eq!(err.code, ErrorCode::Other);
+            assert!(!err.message().is_empty());
 
-            // Clean up
-            let _ = Box::from_raw(result_ptr);
+            free_extern_error((*result).err as *mut ExternError);
+            let _ = Box::from_raw(result);
         }
     }
 
     #[test]
-    fn test_extern_result_ok_optional_some() {
-        let value = Some(42i32);
-        let result_ptr = ExternResult::ok_optional(&value);
+    fn test_error_code_discriminants_are_stable() {
+        assert_eq!(ErrorCode::Other as i32, 0);
+        assert_eq!(ErrorCode::IoError as i32, 8);
+        assert_eq!(ErrorCode::CancelledError as i32, 9);
+        assert_eq!(ErrorCode::UnimplementedError as i32, 10);
+    }
+
+    #[test]
+    fn test_error_builder_without_detail() {
+        let error = ExternErrorBuilder::new(ErrorCode::ValidationError)
+            .message("Name is required")
+            .build();
 
         unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(!result.ok.is_null());
-            assert!(result.err.is_null());
+            assert_eq!((*error).code(), ErrorCode::ValidationError);
+            assert_eq!((*error).message(), "Name is required");
+            assert!((*error).detail.is_null());
+            assert_eq!((*error).detail(), None);
 
-            // Clean up - need to free the value inside
-            let _ = Box::from_raw(result.ok as *mut i32);
-            let _ = Box::from_raw(result_ptr);
+            free_extern_error(error);
         }
     }
 
     #[test]
-    fn test_extern_result_ok_optional_none() {
-        let value: Option<i32> = None;
-        let result_ptr = ExternResult::ok_optional(&value);
+    fn test_error_builder_with_detail() {
+        let error = ExternErrorBuilder::new(ErrorCode::Other)
+            .code(ErrorCode::TimeoutError)
+            .message("Upstream timed out")
+            .detail("retry_after=30")
+            .build();
 
         unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(result.ok.is_null());
-            assert!(result.err.is_null());
+            assert_eq!((*error).code(), ErrorCode::TimeoutError);
+            assert_eq!((*error).message(), "Upstream timed out");
+            assert_eq!((*error).detail(), Some("retry_after=30"));
 
-            // Clean up
-            let _ = Box::from_raw(result_ptr);
+            free_extern_error(error);
         }
     }
 
     #[test]
-    fn test_extern_result_err() {
-        let result_ptr = ExternResult::err(ErrorCode::AuthenticationError, "Auth failed");
+    fn test_error_builder_into_result() {
+        let result = ExternErrorBuilder::new(ErrorCode::NetworkError)
+            .message("Unreachable")
+            .detail("ECONNREFUSED")
+            .into_result();
+
+        let error = unsafe { extern_result_into_result::<()>(result) }.unwrap_err();
+        assert_eq!(error.code, ErrorCode::NetworkError);
+        assert_eq!(error.message, "Unreachable");
+        assert_eq!(error.detail.as_deref(), Some("ECONNREFUSED"));
+    }
 
-        unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(result.ok.is_null());
-            assert!(!result.err.is_null());
+    #[test]
+    fn test_err_has_no_detail() {
+        let owned =
+            unsafe { OwnedExternResult::from_raw(ExternResult::err(ErrorCode::Other, "Plain")) };
 
-            let error = &*result.err;
-            assert!(!error.message.is_null());
+        assert_eq!(owned.error().unwrap().detail(), None);
+    }
 
-            // Verify error message
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            let message = c_str.to_str().unwrap();
-            assert_eq!(message, "Auth failed");
+    #[test]
+    fn test_extern_result_abi_version() {
+        assert_eq!(extern_result_abi_version(), EXTERN_RESULT_ABI_VERSION);
+
+        let results = [
+            ExternResult::ok(1u8),
+            ExternResult::ok_null(),
+            ExternResult::err(ErrorCode::Other, "Failure"),
+            ExternResult::from(Ok::<u8, TestError>(2)).into_boxed_raw(),
+        ];
+        for result in results {
+            // C reads the version first, before relying on any other field
+            let version = unsafe { *(result as *const u32) };
+            assert_eq!(version, extern_result_abi_version());
+            let _ = unsafe { extern_result_into_result::<u8>(result) };
+        }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+        let mut storage = core::mem::MaybeUninit::<ExternResult>::uninit();
+        unsafe {
+            extern_result_init_ok(storage.as_mut_ptr(), 3u8);
+            let result = storage.assume_init();
+            assert_eq!(result.version, EXTERN_RESULT_ABI_VERSION);
+            let _ = Box::from_raw(result.ok as *mut u8);
         }
     }
 
     #[test]
-    fn test_extern_result_err_with_string() {
-        let error_msg = String::from("Error message");
-        let result_ptr = ExternResult::err(ErrorCode::Other, error_msg.clone());
+    fn test_extern_result_tag() {
+        let ok = ExternResult::ok(1u8);
+        let null_value = ExternResult::ok_ptr(std::ptr::null_mut::<u8>());
+        let ok_null = ExternResult::ok_null();
+        let err = ExternResult::err(ErrorCode::Other, "Failure");
+
+        assert_eq!(extern_result_tag(ok), ExternResultTag::Ok);
+        assert_eq!(extern_result_tag(null_value), ExternResultTag::Ok);
+        assert_eq!(extern_result_tag(ok_null), ExternResultTag::OkNull);
+        assert_eq!(extern_result_tag(err), ExternResultTag::Err);
+        assert_eq!(extern_result_tag(std::ptr::null()), ExternResultTag::Err);
+        assert_eq!(
+            unsafe { CStr::from_ptr(extern_result_tag_name(ExternResultTag::OkNull)) },
+            c"OkNull"
+        );
 
-        unsafe {
-            assert!(!result_ptr.is_null());
-            let result = &*result_ptr;
-            assert!(result.ok.is_null());
-            assert!(!result.err.is_null());
+        for result in [ok, null_value, ok_null, err] {
+            let _ = unsafe { extern_result_into_result::<u8>(result) };
+        }
+    }
 
-            let error = &*result.err;
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            let message = c_str.to_str().unwrap();
-            assert_eq!(message, error_msg);
+    #[test]
+    fn test_extern_result_accessors_ok() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::ok(5u8)) };
+        let ptr = owned.as_ref() as *const ExternResult;
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+        assert!(extern_result_is_ok(ptr));
+        assert!(!extern_result_get_ok(ptr).is_null());
+        assert!(extern_result_get_err(ptr).is_null());
+
+        unsafe {
+            assert_eq!(*(extern_result_get_ok(ptr) as *const u8), 5);
+            let _ = Box::from_raw(extern_result_get_ok(ptr) as *mut u8);
         }
     }
 
     #[test]
-    fn test_from_result_ok() {
-        let rust_result: Result<i32, TestError> = Ok(123);
-        let extern_result = ExternResult::from(rust_result);
+    fn test_extern_result_accessors_ok_null() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::ok_null()) };
+        let ptr = owned.as_ref() as *const ExternResult;
 
-        assert!(!extern_result.ok.is_null());
-        assert!(extern_result.err.is_null());
+        assert!(extern_result_is_ok(ptr));
+        assert!(extern_result_get_ok(ptr).is_null());
+        assert!(extern_result_get_err(ptr).is_null());
+    }
+
+    #[test]
+    fn test_has_value_distinguishes_ok_null_from_null_payload() {
+        let empty = unsafe { OwnedExternResult::from_raw(ExternResult::ok_null()) };
+        let null_payload = unsafe {
+            OwnedExternResult::from_raw(ExternResult::ok_ptr(std::ptr::null_mut::<u8>()))
+        };
+
+        assert_eq!(empty.as_ref().has_value, 0);
+        assert_eq!(null_payload.as_ref().has_value, 1);
+        assert!(!extern_result_has_value(empty.as_ref()));
+        assert!(extern_result_has_value(null_payload.as_ref()));
+        assert!(extern_result_get_ok(null_payload.as_ref()).is_null());
+    }
+
+    #[test]
+    fn test_has_value_for_ok_and_err() {
+        let ok = ExternResult::ok(5u32);
+        let err = ExternResult::err(ErrorCode::Other, "Failed");
+        let converted = ExternResult::from(Ok::<u32, TestError>(6));
 
         unsafe {
-            let value = *(extern_result.ok as *const i32);
-            assert_eq!(value, 123);
+            assert_eq!((*ok).has_value, 1);
+            assert_eq!((*err).has_value, 0);
+            assert_eq!(converted.has_value, 1);
+        }
+        assert!(!extern_result_has_value(err));
+        assert!(!extern_result_has_value(std::ptr::null()));
 
-            // Clean up
-            let _ = Box::from_raw(extern_result.ok as *mut i32);
+        unsafe {
+            drop(extern_result_into_result::<u32>(ok));
+            drop(extern_result_into_result::<u32>(err));
+            let _ = Box::from_raw(converted.ok as *mut u32);
         }
     }
 
     #[test]
-    fn test_from_result_err() {
-        let rust_result: Result<i32, TestError> = Err(TestError {
-            message: String::from("Test error"),
+    fn test_extern_result_accessors_err() {
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err(ErrorCode::IoError, "Disk full"))
+        };
+        let ptr = owned.as_ref() as *const ExternResult;
+
+        assert!(!extern_result_is_ok(ptr));
+        assert!(extern_result_get_ok(ptr).is_null());
+        let error = unsafe { &*extern_result_get_err(ptr) };
+        assert_eq!(error.code(), ErrorCode::IoError);
+        assert_eq!(error.message(), "Disk full");
+    }
+
+    #[test]
+    fn test_extern_result_accessors_null() {
+        assert!(!extern_result_is_ok(std::ptr::null()));
+        assert!(extern_result_get_ok(std::ptr::null()).is_null());
+        assert!(extern_result_get_err(std::ptr::null()).is_null());
+    }
+
+    #[test]
+    fn test_from_unit_result_ok() {
+        let rust_result: Result<(), TestError> = Ok(());
+        let owned =
+            unsafe { OwnedExternResult::from_raw(ExternResult::from_unit_result(rust_result)) };
+
+        assert!(owned.as_ref().ok.is_null());
+        assert!(owned.as_ref().err.is_null());
+    }
+
+    #[test]
+    fn test_from_unit_result_err() {
+        let rust_result: Result<(), TestError> = Err(TestError {
+            message: String::from("Write failed"),
         });
-        let extern_result = ExternResult::from(rust_result);
+        let owned =
+            unsafe { OwnedExternResult::from_raw(ExternResult::from_unit_result(rust_result)) };
 
-        assert!(extern_result.ok.is_null());
-        assert!(!extern_result.err.is_null());
+        assert!(owned.as_ref().ok.is_null());
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert_eq!(error.message(), "Write failed");
+    }
 
-        unsafe {
-            let error = &*extern_result.err;
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            let message = c_str.to_str().unwrap();
-            assert_eq!(message, "Test error");
+    #[test]
+    fn test_from_bytes_result_ok() {
+        let rust_result: Result<Vec<u8>, TestError> = Ok(vec![0xde, 0xad, 0xbe, 0xef]);
+        let result = ExternResult::from_bytes_result(rust_result);
+
+        assert!(extern_result_is_ok(result));
+        let buf = unsafe { &*(extern_result_get_ok(result) as *const ExternBuffer) };
+        assert_eq!(buf.len, 4);
+        assert_eq!(
+            unsafe { crate::buffer::extern_buffer_to_vec(buf) },
+            [0xde, 0xad, 0xbe, 0xef]
+        );
+        extern_bytes_result_destroy(result);
+    }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(extern_result.err as *mut ExternError);
-        }
+    #[test]
+    fn test_from_bytes_result_err() {
+        let rust_result: Result<Vec<u8>, TestError> = Err(TestError {
+            message: String::from("Decryption failed"),
+        });
+        let result = ExternResult::from_bytes_result(rust_result);
+
+        assert!(extern_result_get_ok(result).is_null());
+        let error = unsafe { &*extern_result_get_err(result) };
+        assert_eq!(error.code(), ErrorCode::Other);
+        assert_eq!(error.message(), "Decryption failed");
+        extern_bytes_result_destroy(result);
+        extern_bytes_result_destroy(core::ptr::null_mut());
     }
 
     #[test]
-    fn test_error_code_variants() {
-        // Test both error code variants
-        let auth_err = ExternResult::err(ErrorCode::AuthenticationError, "Auth error");
-        let other_err = ExternResult::err(ErrorCode::Other, "Other error");
+    fn test_scalar_result_f64() {
+        let ok = ExternResultF64::ok(2.5);
+        assert!(!ok.is_err);
+        assert!(ok.err.is_null());
+        assert_eq!(ok.value, 2.5);
+
+        let err = ExternResultF64::err(ErrorCode::ValidationError, "Not a number");
+        assert!(err.is_err);
+        assert_eq!(err.value, 0.0);
+        unsafe { assert_eq!((*err.err).message(), "Not a number") };
+        free_extern_error(err.err as *mut ExternError);
+    }
 
+    #[test]
+    fn test_scalar_result_float_nan() {
+        // NaN is a legitimate value, not an error
+        let nan = ExternResultF64::ok(f64::NAN);
+        assert!(!nan.is_err);
+        assert!(nan.value.is_nan());
+
+        let nan = ExternResultF32::from(Ok::<f32, TestError>(f32::NAN));
+        assert!(!nan.is_err);
+        assert!(nan.value.is_nan());
+    }
+
+    #[test]
+    fn test_scalar_result_i128() {
+        let ok = ExternResultI128::ok(i128::MIN);
+        assert!(!ok.is_err);
+        assert_eq!(ok.value, i128::MIN);
+
+        let err = ExternResultI128::from(Err::<i128, TestError>(TestError {
+            message: String::from("Overflow"),
+        }));
+        assert!(err.is_err);
+        assert_eq!(err.value, 0);
         unsafe {
-            let auth_error = &*(&*auth_err).err;
-            let other_error = &*(&*other_err).err;
+            assert_eq!((*err.err).code(), ErrorCode::Other);
+            assert_eq!((*err.err).message(), "Overflow");
+        }
+        free_extern_error(err.err as *mut ExternError);
+    }
 
-            // Verify we can distinguish error codes
-            match auth_error.code {
-                ErrorCode::AuthenticationError => {}
-                _ => panic!("Expected AuthenticationError"),
-            }
+    #[test]
+    fn test_scalar_result_u128() {
+        let ok = ExternResultU128::ok(u128::MAX);
+        assert!(!ok.is_err);
+        assert_eq!(ok.value, u128::MAX);
+
+        let err = ExternResultU128::err(ErrorCode::InvalidArgumentError, "Negative input");
+        assert!(err.is_err);
+        assert_eq!(err.value, 0);
+        free_extern_error(err.err as *mut ExternError);
+    }
 
-            match other_error.code {
-                ErrorCode::Other => {}
-                _ => panic!("Expected Other error"),
-            }
+    #[test]
+    fn test_scalar_result_layout() {
+        assert_eq!(
+            std::mem::align_of::<ExternResultU128>(),
+            std::mem::align_of::<u128>()
+        );
+        assert_eq!(std::mem::offset_of!(ExternResultU128, value), 0);
+        assert_eq!(std::mem::offset_of!(ExternResultF64, value), 0);
+    }
 
-            // Clean up
-            let _ = CString::from_raw(auth_error.message as *mut _);
-            let _ = Box::from_raw((&*auth_err).err as *mut ExternError);
-            let _ = Box::from_raw(auth_err);
+    #[repr(align(64))]
+    #[derive(Debug, PartialEq)]
+    struct CacheLine([u8; 64]);
 
-            let _ = CString::from_raw(other_error.message as *mut _);
-            let _ = Box::from_raw((&*other_err).err as *mut ExternError);
-            let _ = Box::from_raw(other_err);
+    define_result_destructor!(test_cache_line_result_destroy, CacheLine);
+
+    // Run under `cargo miri test` to check the value is freed with its original layout
+    #[test]
+    fn test_result_destructor_over_aligned() {
+        let result_ptr = ExternResult::ok(CacheLine([7; 64]));
+        let ok = extern_result_get_ok(result_ptr);
+
+        assert_eq!(ok as usize % 64, 0);
+        assert_eq!(unsafe { &*(ok as *const CacheLine) }, &CacheLine([7; 64]));
+        test_cache_line_result_destroy(result_ptr);
+
+        test_cache_line_result_destroy(ExternResult::err(ErrorCode::Other, "No value"));
+        test_cache_line_result_destroy(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_ok_nonnull_matches_raw() {
+        let result = ExternResult::ok_nonnull(11u16);
+        let raw = ExternResult::into_raw(result);
+
+        assert_eq!(raw, result.as_ptr());
+        unsafe {
+            assert_eq!(*((*raw).ok as *const u16), 11);
+            let _ = Box::from_raw((*raw).ok as *mut u16);
+            drop(OwnedExternResult::from_raw(raw));
         }
     }
 
     #[test]
-    fn test_extern_result_destroy() {
-        // Test that the destructor doesn't crash with a valid pointer
-        let result_ptr = ExternResult::ok(42i32);
+    fn test_ok_ptr_and_ok_null_nonnull() {
+        let value = Box::into_raw(Box::new(3i64));
+        let with_value = ExternResult::ok_ptr_nonnull(value);
+        let without_value = ExternResult::ok_null_nonnull();
 
         unsafe {
-            // First free the inner value
-            let result = &*result_ptr;
-            let _ = Box::from_raw(result.ok as *mut i32);
+            assert_eq!(with_value.as_ref().ok, value as *const c_void);
+            assert!(without_value.as_ref().ok.is_null());
+
+            let _ = Box::from_raw(value);
+            drop(OwnedExternResult::from_raw(ExternResult::into_raw(
+                with_value,
+            )));
+            drop(OwnedExternResult::from_raw(ExternResult::into_raw(
+                without_value,
+            )));
         }
+    }
 
-        // Now destroy the ExternResult itself
-        extern_result_destroy(result_ptr);
+    #[test]
+    fn test_err_nonnull() {
+        let result = ExternResult::err_nonnull(ErrorCode::PermissionError, "Denied");
+        let raw = ExternResult::into_raw(result);
+
+        assert_eq!(raw, result.as_ptr());
+        let owned = unsafe { OwnedExternResult::from_raw(raw) };
+        assert_eq!(owned.error().unwrap().code(), ErrorCode::PermissionError);
+        assert_eq!(owned.error().unwrap().message(), "Denied");
     }
 
     #[test]
-    fn test_multiple_extern_results() {
-        // Create multiple results to ensure no memory conflicts
-        let results: Vec<*mut ExternResult> = (0..10).map(|i| ExternResult::ok(i)).collect();
+    fn test_err_from_classified_error() {
+        #[derive(Debug)]
+        enum AccountError {
+            Locked,
+            Missing(u32),
+        }
 
-        unsafe {
-            for result_ptr in results {
-                let result = &*result_ptr;
-                assert!(!result.ok.is_null());
-                assert!(result.err.is_null());
+        impl std::fmt::Display for AccountError {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    AccountError::Locked => write!(f, "Account is locked"),
+                    AccountError::Missing(id) => write!(f, "Account {} not found", id),
+                }
+            }
+        }
+
+        impl std::error::Error for AccountError {}
+
+        impl ErrorClassify for AccountError {
+            fn error_code(&self) -> ErrorCode {
+                match self {
+                    AccountError::Locked => ErrorCode::PermissionError,
+                    AccountError::Missing(_) => ErrorCode::NotFoundError,
+                }
+            }
+        }
+
+        let owned =
+            unsafe { OwnedExternResult::from_raw(ExternResult::err_from(AccountError::Locked)) };
+        assert_eq!(owned.error().unwrap().code(), ErrorCode::PermissionError);
+        assert_eq!(owned.error().unwrap().message(), "Account is locked");
+
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err_from(AccountError::Missing(7)))
+        };
+        assert_eq!(owned.error().unwrap().code(), ErrorCode::NotFoundError);
+        assert_eq!(owned.error().unwrap().message(), "Account 7 not found");
+    }
+
+    #[test]
+    fn test_err_from_default_classification() {
+        let error = TestError {
+            message: String::from("Unclassified"),
+        };
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::err_from(error)) };
 
-                // Clean up
-                let _ = Box::from_raw(result.ok as *mut i32);
-                let _ = Box::from_raw(result_ptr);
-            }
-        }
+        assert_eq!(owned.error().unwrap().code(), ErrorCode::Other);
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test_extern_result_with_complex_type() {
-        #[derive(Debug, PartialEq)]
-        struct ComplexType {
-            id: u64,
-            name: String,
-            values: Vec<i32>,
-        }
+    fn test_err_from_io_error() {
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "config.toml");
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::err_from(error)) };
 
-        let complex = ComplexType {
-            id: 123,
-            name: String::from("Test"),
-            values: vec![1, 2, 3, 4, 5],
-        };
+        assert_eq!(owned.error().unwrap().code(), ErrorCode::IoError);
+        assert_eq!(owned.error().unwrap().message(), "config.toml");
+    }
 
-        let result_ptr = ExternResult::ok(complex);
+    #[test]
+    fn test_ok_with_warning() {
+        let result_ptr = ExternResult::ok_with_warning(10u32, "Value was clamped");
 
         unsafe {
             let result = &*result_ptr;
             assert!(!result.ok.is_null());
-
-            let value = &*(result.ok as *const ComplexType);
-            assert_eq!(value.id, 123);
-            assert_eq!(value.name, "Test");
-            assert_eq!(value.values, vec![1, 2, 3, 4, 5]);
-
-            // Clean up
-            let _ = Box::from_raw(result.ok as *mut ComplexType);
-            let _ = Box::from_raw(result_ptr);
+            assert!(result.err.is_null());
+            assert_eq!(*(result.ok as *const u32), 10);
+            assert_eq!(
+                crate::string::c_char_to_string(extern_result_get_warning(result_ptr)),
+                "Value was clamped"
+            );
+
+            // The destructor frees the warning along with the result
+            let _ = Box::from_raw(result.ok as *mut u32);
         }
+        extern_result_destroy(result_ptr);
     }
 
     #[test]
-    fn test_extern_error_unicode_message() {
-        let result_ptr = ExternResult::err(ErrorCode::Other, "Error: 错误 🚨");
+    fn test_warning_null_by_default() {
+        let ok = unsafe { OwnedExternResult::from_raw(ExternResult::ok_null()) };
+        let err =
+            unsafe { OwnedExternResult::from_raw(ExternResult::err(ErrorCode::Other, "Failed")) };
+
+        assert!(ok.as_ref().warning.is_null());
+        assert!(err.as_ref().warning.is_null());
+        assert!(extern_result_get_warning(err.as_ref()).is_null());
+        assert!(extern_result_get_warning(std::ptr::null()).is_null());
+    }
+
+    #[test]
+    fn test_ok_downcast_correct_type() {
+        let result_ptr = ExternResult::ok_tagged(String::from("tagged"));
 
         unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            let message = c_str.to_str().unwrap();
-            assert_eq!(message, "Error: 错误 🚨");
+            let value = ExternResult::ok_downcast::<String>(result_ptr);
+            assert_eq!(value.map(String::as_str), Some("tagged"));
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+            let _ = extern_result_into_result::<String>(result_ptr);
         }
     }
 
     #[test]
-    fn test_all_error_code_variants() {
-        // Test all error code variants to ensure they can be created and used
-        let test_cases = vec![
-            (ErrorCode::Other, "Generic error"),
-            (ErrorCode::AuthenticationError, "Auth failed"),
-            (ErrorCode::ValidationError, "Invalid input"),
-            (ErrorCode::NotFoundError, "Resource not found"),
-            (ErrorCode::PermissionError, "Access denied"),
-            (ErrorCode::TimeoutError, "Request timed out"),
-            (ErrorCode::NetworkError, "Network unavailable"),
-            (ErrorCode::InvalidArgumentError, "Bad argument"),
-            (ErrorCode::IoError, "File read failed"),
-        ];
+    fn test_ok_downcast_null_ok() {
+        let owned = unsafe { OwnedExternResult::from_raw(ExternResult::ok_null()) };
 
-        for (code, message) in test_cases {
-            let result_ptr = ExternResult::err(code, message);
+        assert!(unsafe { ExternResult::ok_downcast::<u8>(owned.as_ref()) }.is_none());
+    }
 
-            unsafe {
-                assert!(!result_ptr.is_null());
-                let result = &*result_ptr;
-                assert!(result.ok.is_null());
-                assert!(!result.err.is_null());
+    #[test]
+    fn test_ok_as_mut() {
+        let result_ptr = ExternResult::ok(41i32);
 
-                let error = &*result.err;
-                let c_str = std::ffi::CStr::from_ptr(error.message);
-                let msg_str = c_str.to_str().unwrap();
-                assert_eq!(msg_str, message);
+        unsafe {
+            *ExternResult::ok_as_mut::<i32>(result_ptr).unwrap() += 1;
+            assert_eq!(*(extern_result_get_ok(result_ptr) as *const i32), 42);
 
-                // Clean up
-                let _ = CString::from_raw(error.message as *mut _);
-                let _ = Box::from_raw(result.err as *mut ExternError);
-                let _ = Box::from_raw(result_ptr);
-            }
+            let _ = extern_result_into_result::<i32>(result_ptr);
         }
     }
 
     #[test]
-    fn test_validation_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::ValidationError,
-            "Email format is invalid"
-        );
+    fn test_ok_as_mut_null() {
+        let result_ptr = ExternResult::ok_null();
 
         unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
-
-            match error.code {
-                ErrorCode::ValidationError => {}
-                _ => panic!("Expected ValidationError"),
-            }
-
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            assert_eq!(c_str.to_str().unwrap(), "Email format is invalid");
-
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+            assert!(ExternResult::ok_as_mut::<i32>(result_ptr).is_none());
+            assert!(ExternResult::ok_as_mut::<i32>(std::ptr::null_mut()).is_none());
         }
+        extern_result_destroy(result_ptr);
     }
 
+    #[cfg(all(debug_assertions, feature = "std"))]
     #[test]
-    fn test_not_found_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::NotFoundError,
-            "User with ID 123 not found"
-        );
+    fn test_ok_downcast_wrong_type_panics() {
+        let result_ptr = ExternResult::ok_tagged(5u64);
 
-        unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
+        let outcome = std::panic::catch_unwind(|| unsafe {
+            ExternResult::ok_downcast::<String>(result_ptr).map(String::len)
+        });
 
-            match error.code {
-                ErrorCode::NotFoundError => {}
-                _ => panic!("Expected NotFoundError"),
-            }
+        // `type_name` output is not stable, so only check the plain `u64`
+        let message = crate::export::panic_message(outcome.unwrap_err());
+        assert!(message.contains("ok value is a `u64`"), "{}", message);
 
-            let c_str = std::ffi::CStr::from_ptr(error.message);
-            assert_eq!(c_str.to_str().unwrap(), "User with ID 123 not found");
+        let _ = unsafe { extern_result_into_result::<u64>(result_ptr) };
+    }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
-        }
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    fn test_ok_tag_removed_when_value_freed() {
+        let is_tagged =
+            |ok: *const c_void| ok_type_tags().lock().unwrap().contains_key(&(ok as usize));
+
+        let result_ptr = ExternResult::ok_tagged(5u64);
+        let ok = unsafe { (*result_ptr).ok };
+        assert!(is_tagged(ok));
+        let _ = unsafe { extern_result_into_result::<u64>(result_ptr) };
+        assert!(!is_tagged(ok));
+
+        let result_ptr = ExternResult::ok_tagged(6u64);
+        let taken = unsafe { extern_result_take_ok(result_ptr) };
+        assert!(!is_tagged(taken));
+        let _ = unsafe { Box::from_raw(taken as *mut u64) };
+        extern_result_destroy(result_ptr);
     }
 
+    #[cfg(all(debug_assertions, feature = "std"))]
     #[test]
-    fn test_permission_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::PermissionError,
-            "Insufficient permissions to access resource"
-        );
+    fn test_ok_tag_cleared_by_every_ok_path() {
+        // Freeing a tagged value by hand leaves its tag behind, and the allocator
+        // usually hands the same address to the next value of that size
+        let leave_stale_tag = || unsafe {
+            let result_ptr = ExternResult::ok_tagged(1i64);
+            let _ = Box::from_raw((*result_ptr).ok as *mut i64);
+            extern_result_destroy(result_ptr);
+        };
 
+        leave_stale_tag();
+        let converted = ExternResult::from(Ok::<_, std::io::Error>(2u64)).into_boxed_raw();
         unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
-
-            match error.code {
-                ErrorCode::PermissionError => {}
-                _ => panic!("Expected PermissionError"),
-            }
+            assert_eq!(ExternResult::ok_downcast::<u64>(converted), Some(&2));
+            let _ = extern_result_into_result::<u64>(converted);
+        }
 
-            // Clean up
-            let _ = CString::from_raw((&*result.err).message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+        leave_stale_tag();
+        let mut out = std::mem::MaybeUninit::<ExternResult>::uninit();
+        unsafe {
+            extern_result_init_ok(out.as_mut_ptr(), 3u64);
+            assert_eq!(ExternResult::ok_downcast::<u64>(out.as_ptr()), Some(&3));
+            let _ = Box::from_raw(out.assume_init_ref().ok as *mut u64);
+            out.assume_init_drop();
         }
+
+        leave_stale_tag();
+        let mut arena = crate::arena::ResultArena::new();
+        let result_ptr = arena.ok(4u64);
+        assert_eq!(
+            unsafe { ExternResult::ok_downcast::<u64>(result_ptr) },
+            Some(&4)
+        );
     }
 
+    #[cfg(debug_assertions)]
     #[test]
-    fn test_timeout_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::TimeoutError,
-            "Operation exceeded 30 second timeout"
-        );
+    fn test_ok_as_mut_misaligned_panics() {
+        let mut storage = [0u64; 2];
+        let misaligned = unsafe { (storage.as_mut_ptr() as *mut u8).add(1) } as *mut u64;
+        let result_ptr = ExternResult::ok_ptr(misaligned);
 
-        unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
+        let outcome = std::panic::catch_unwind(|| unsafe {
+            ExternResult::ok_as_mut::<u64>(result_ptr).map(|value| *value)
+        });
 
-            match error.code {
-                ErrorCode::TimeoutError => {}
-                _ => panic!("Expected TimeoutError"),
-            }
+        assert!(outcome.is_err());
+        // The value is borrowed, so only the result itself is freed
+        unsafe { drop(Box::from_raw(result_ptr)) };
+    }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
-        }
+    #[test]
+    fn test_error_code_from_errno() {
+        assert_eq!(
+            ErrorCode::from_errno(libc::ENOENT),
+            ErrorCode::NotFoundError
+        );
+        assert_eq!(
+            ErrorCode::from_errno(libc::EACCES),
+            ErrorCode::PermissionError
+        );
+        assert_eq!(
+            ErrorCode::from_errno(libc::EPERM),
+            ErrorCode::PermissionError
+        );
+        assert_eq!(
+            ErrorCode::from_errno(libc::ETIMEDOUT),
+            ErrorCode::TimeoutError
+        );
+        assert_eq!(
+            ErrorCode::from_errno(libc::ECONNREFUSED),
+            ErrorCode::NetworkError
+        );
+        assert_eq!(
+            ErrorCode::from_errno(libc::EINVAL),
+            ErrorCode::InvalidArgumentError
+        );
+        assert_eq!(ErrorCode::from_errno(libc::EIO), ErrorCode::IoError);
+        assert_eq!(ErrorCode::from_errno(libc::ENOSPC), ErrorCode::IoError);
+        assert_eq!(ErrorCode::from_errno(0), ErrorCode::Other);
+        assert_eq!(ErrorCode::from_errno(-1), ErrorCode::Other);
     }
 
     #[test]
-    fn test_network_error() {
-        let result_ptr = ExternResult::err(
+    fn test_error_code_errno_round_trip() {
+        for code in [
+            ErrorCode::NotFoundError,
+            ErrorCode::PermissionError,
+            ErrorCode::TimeoutError,
             ErrorCode::NetworkError,
-            "Failed to connect to server: connection refused"
-        );
+            ErrorCode::InvalidArgumentError,
+            ErrorCode::IoError,
+            ErrorCode::CancelledError,
+            ErrorCode::UnimplementedError,
+        ] {
+            assert_eq!(ErrorCode::from_errno(code.to_errno()), code);
+        }
+    }
 
-        unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
+    #[test]
+    fn test_error_code_u8_round_trip() {
+        for &code in ErrorCode::VARIANTS {
+            assert_eq!(i32::from(code.to_u8()), i32::from(code));
+            assert_eq!(ErrorCode::from_u8(code.to_u8()), Some(code));
+        }
+        assert_eq!(ErrorCode::IoError.to_u8(), 8);
+    }
 
-            match error.code {
-                ErrorCode::NetworkError => {}
-                _ => panic!("Expected NetworkError"),
-            }
+    #[test]
+    fn test_error_code_from_u8_rejects_unknown() {
+        assert_eq!(ErrorCode::from_u8(11), None);
+        assert_eq!(ErrorCode::from_u8(u8::MAX), None);
+    }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+    #[test]
+    fn test_error_code_is_retryable() {
+        let cases = [
+            (ErrorCode::AuthenticationError, false),
+            (ErrorCode::ValidationError, false),
+            (ErrorCode::NotFoundError, false),
+            (ErrorCode::PermissionError, false),
+            (ErrorCode::TimeoutError, true),
+            (ErrorCode::NetworkError, true),
+            (ErrorCode::InvalidArgumentError, false),
+            (ErrorCode::CancelledError, false),
+            (ErrorCode::UnimplementedError, false),
+        ];
+        for (code, retryable) in cases {
+            assert_eq!(code.is_retryable(), retryable);
+            assert_eq!(error_code_is_retryable(code), retryable);
+            // Fixed classifications cannot be changed
+            assert!(!set_retryable(code, !retryable));
+            assert_eq!(code.is_retryable(), retryable);
+        }
+
+        // The configurable codes default to not retryable
+        for code in [ErrorCode::IoError, ErrorCode::Other] {
+            assert!(!code.is_retryable());
+            assert!(set_retryable(code, true));
+            assert!(error_code_is_retryable(code));
+            assert!(set_retryable(code, false));
+            assert!(!code.is_retryable());
         }
     }
 
     #[test]
-    fn test_invalid_argument_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::InvalidArgumentError,
-            "Argument 'count' must be positive"
-        );
+    fn test_error_code_http_status() {
+        let cases = [
+            (ErrorCode::Other, 500),
+            (ErrorCode::AuthenticationError, 401),
+            (ErrorCode::ValidationError, 400),
+            (ErrorCode::NotFoundError, 404),
+            (ErrorCode::PermissionError, 403),
+            (ErrorCode::TimeoutError, 504),
+            (ErrorCode::NetworkError, 502),
+            (ErrorCode::InvalidArgumentError, 400),
+            (ErrorCode::IoError, 500),
+            (ErrorCode::CancelledError, 499),
+            (ErrorCode::UnimplementedError, 501),
+        ];
+        assert_eq!(cases.len(), ErrorCode::VARIANTS.len());
 
-        unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
+        for (code, status) in cases {
+            assert_eq!(code.http_status(), status);
+            assert_eq!(error_code_http_status(code), status);
+        }
+    }
 
-            match error.code {
-                ErrorCode::InvalidArgumentError => {}
-                _ => panic!("Expected InvalidArgumentError"),
-            }
+    #[test]
+    fn test_error_code_try_from() {
+        assert_eq!(ErrorCode::VARIANTS.len(), 11);
+        for (i, &code) in ErrorCode::VARIANTS.iter().enumerate() {
+            assert_eq!(code as i32, i as i32);
+            assert_eq!(ErrorCode::try_from(i as i32), Ok(code));
+        }
+        assert_eq!(ErrorCode::try_from(11), Err(11));
+        assert_eq!(ErrorCode::try_from(-1), Err(-1));
+    }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+    #[test]
+    fn test_error_code_name() {
+        let cases = [
+            (ErrorCode::Other, "Other"),
+            (ErrorCode::AuthenticationError, "AuthenticationError"),
+            (ErrorCode::ValidationError, "ValidationError"),
+            (ErrorCode::NotFoundError, "NotFoundError"),
+            (ErrorCode::PermissionError, "PermissionError"),
+            (ErrorCode::TimeoutError, "TimeoutError"),
+            (ErrorCode::NetworkError, "NetworkError"),
+            (ErrorCode::InvalidArgumentError, "InvalidArgumentError"),
+            (ErrorCode::IoError, "IoError"),
+            (ErrorCode::CancelledError, "CancelledError"),
+            (ErrorCode::UnimplementedError, "UnimplementedError"),
+        ];
+        for (code, expected) in cases {
+            let name = unsafe { CStr::from_ptr(error_code_name(code)) };
+            assert_eq!(name.to_str().unwrap(), expected);
+            assert_eq!(expected, format!("{:?}", code));
         }
     }
 
     #[test]
-    fn test_io_error() {
-        let result_ptr = ExternResult::err(
-            ErrorCode::IoError,
-            "Failed to read file: permission denied"
+    fn test_error_code_name_is_static() {
+        for &code in ErrorCode::VARIANTS {
+            // Each name points into a static table, so nothing is allocated per call
+            assert_eq!(error_code_name(code), error_code_name(code));
+            assert_eq!(error_code_name(code), code.name().as_ptr());
+        }
+        assert_ne!(
+            error_code_name(ErrorCode::IoError),
+            error_code_name(ErrorCode::Other)
         );
+    }
 
-        unsafe {
-            let result = &*result_ptr;
-            let error = &*result.err;
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_err_is_logged() {
+        use std::sync::Mutex;
 
-            match error.code {
-                ErrorCode::IoError => {}
-                _ => panic!("Expected IoError"),
+        struct CapturingLogger(Mutex<Vec<(log::Level, String)>>);
+
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
             }
 
-            // Clean up
-            let _ = CString::from_raw(error.message as *mut _);
-            let _ = Box::from_raw(result.err as *mut ExternError);
-            let _ = Box::from_raw(result_ptr);
+            fn log(&self, record: &log::Record) {
+                let entry = (record.level(), record.args().to_string());
+                self.0.lock().unwrap().push(entry);
+            }
+
+            fn flush(&self) {}
         }
+
+        static LOGGER: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let owned = unsafe {
+            OwnedExternResult::from_raw(ExternResult::err(
+                ErrorCode::NetworkError,
+                "Logged failure",
+            ))
+        };
+        set_error_log_level(log::Level::Warn);
+        let rust_result: Result<(), TestError> = Err(TestError {
+            message: String::from("Logged at warn"),
+        });
+        let converted = ExternResult::from(rust_result);
+        set_error_log_level(log::Level::Error);
+
+        let records = LOGGER.0.lock().unwrap();
+        assert!(records.contains(&(
+            log::Level::Error,
+            String::from("NetworkError: Logged failure")
+        )));
+        assert!(records.contains(&(log::Level::Warn, String::from("Other: Logged at warn"))));
+
+        drop(owned);
+        free_extern_error(converted.err as *mut ExternError);
     }
 
     #[test]
@@ -675,4 +3769,32 @@ mod tests {
             let _ = Box::from_raw(io_err);
         }
     }
+
+    // Only the `core` and `alloc` parts of the API are exercised here, mirroring
+    // what a `no_std` crate sees.
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn test_result_without_std() {
+        use alloc::string::String;
+
+        let ok = ExternResult::ok(String::from("no_std"));
+        let err = Box::into_raw(Box::new(ExternResult::from(Err::<u8, _>(
+            OwnedExternError {
+                code: ErrorCode::Other,
+                message: String::from("failed"),
+                detail: None,
+            },
+        ))));
+
+        unsafe {
+            assert_eq!(
+                *extern_result_into_result::<String>(ok).unwrap().unwrap(),
+                "no_std"
+            );
+            assert_eq!(
+                extern_result_into_result::<u8>(err).unwrap_err().message,
+                "Other: failed"
+            );
+        }
+    }
 }
@@ -0,0 +1,186 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// A contiguous array of `T` handed to C as a `{ data, len }` pair that the
+/// caller can index directly.
+///
+/// `T` must be `#[repr(C)]` (or a primitive) so C sees the same element layout.
+/// An empty slice is represented by a null `data` pointer and a `len` of 0.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// Use `define_slice_destructor!` to create a destructor for each element type.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExternSlice<T> {
+    pub data: *mut T,
+    pub len: usize,
+}
+
+impl<T> ExternSlice<T> {
+    /// Leaks `vec` as an exact-capacity allocation and returns a pointer/length
+    /// pair describing it.
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        if vec.is_empty() {
+            return ExternSlice {
+                data: core::ptr::null_mut(),
+                len: 0,
+            };
+        }
+        // Shrinking to a boxed slice guarantees capacity == len, which is what
+        // the destructor relies on when it rebuilds the `Vec`.
+        let boxed = vec.into_boxed_slice();
+        let len = boxed.len();
+        ExternSlice {
+            data: Box::into_raw(boxed) as *mut T,
+            len,
+        }
+    }
+}
+
+/// Copies a C `double` array of `len` elements into a `Vec<f64>`, bit for bit,
+/// so NaN payloads and signed zeros survive.
+///
+/// A null `ptr`, a `len` of 0 or a `len` too large to be a valid allocation
+/// yields an empty `Vec`.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` readable, aligned `f64`s.
+pub unsafe fn c_f64_array_to_vec(ptr: *const f64, len: usize) -> Vec<f64> {
+    if ptr.is_null() || crate::memory::checked_layout::<f64>(len).is_none() {
+        return Vec::new();
+    }
+    assert_aligned!(ptr, f64);
+    unsafe { core::slice::from_raw_parts(ptr, len) }.to_vec()
+}
+
+/// Hands `values` to C as a `double` array, e.g. the output of a numeric
+/// routine. An empty `Vec` yields a null `data` pointer.
+///
+/// The caller must free the array with `extern_slice_f64_destroy`.
+pub fn vec_f64_to_c_array(values: Vec<f64>) -> ExternSlice<f64> {
+    ExternSlice::from_vec(values)
+}
+
+/// Releases an array returned by `vec_f64_to_c_array`. An empty array is ignored.
+#[unsafe(export_name = ffi_symbol!("extern_slice_f64_destroy"))]
+pub extern "C" fn extern_slice_f64_destroy(slice: ExternSlice<f64>) {
+    if !slice.data.is_null() {
+        let _ = unsafe { Vec::from_raw_parts(slice.data, slice.len, slice.len) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Point {
+        x: i32,
+        y: f64,
+    }
+
+    define_slice_destructor!(destroy_point_slice, Point);
+    define_slice_destructor!(destroy_u32_slice, u32);
+
+    #[test]
+    fn test_from_vec_indexing() {
+        let points = vec![
+            Point { x: 1, y: 1.5 },
+            Point { x: 2, y: 2.5 },
+            Point { x: 3, y: 3.5 },
+        ];
+        let slice = ExternSlice::from_vec(points.clone());
+
+        assert!(!slice.data.is_null());
+        assert_eq!(slice.len, 3);
+
+        unsafe {
+            // Index the way C would: data[i]
+            for (i, expected) in points.iter().enumerate() {
+                let point = *slice.data.add(i);
+                assert_eq!(point.x, expected.x);
+                assert_eq!(point.y, expected.y);
+            }
+        }
+
+        destroy_point_slice(slice);
+    }
+
+    #[test]
+    fn test_from_vec_drops_excess_capacity() {
+        // A Vec with spare capacity must still round-trip through the destructor
+        let mut values: Vec<u32> = Vec::with_capacity(64);
+        values.extend([10, 20, 30]);
+        let slice = ExternSlice::from_vec(values);
+
+        assert_eq!(slice.len, 3);
+        unsafe {
+            assert_eq!(*slice.data.add(2), 30);
+        }
+
+        destroy_u32_slice(slice);
+    }
+
+    #[test]
+    fn test_from_vec_empty() {
+        let slice = ExternSlice::<Point>::from_vec(Vec::new());
+
+        assert!(slice.data.is_null());
+        assert_eq!(slice.len, 0);
+
+        // Destroying an empty slice is a no-op
+        destroy_point_slice(slice);
+    }
+
+    #[test]
+    fn test_f64_array_round_trip_is_bit_exact() {
+        let values = vec![
+            1.5,
+            -0.0,
+            f64::NAN,
+            f64::from_bits(0x7ff8_0000_dead_beef),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN_POSITIVE,
+        ];
+        let bits: Vec<u64> = values.iter().map(|v| v.to_bits()).collect();
+
+        let slice = vec_f64_to_c_array(values);
+        assert_eq!(slice.len, bits.len());
+
+        let copy = unsafe { c_f64_array_to_vec(slice.data, slice.len) };
+        let copy_bits: Vec<u64> = copy.iter().map(|v| v.to_bits()).collect();
+        assert_eq!(copy_bits, bits);
+
+        extern_slice_f64_destroy(slice);
+    }
+
+    #[test]
+    fn test_f64_array_null_and_empty() {
+        assert!(unsafe { c_f64_array_to_vec(core::ptr::null(), 4) }.is_empty());
+        assert!(unsafe { c_f64_array_to_vec([1.0].as_ptr(), 0) }.is_empty());
+        assert!(unsafe { c_f64_array_to_vec([1.0].as_ptr(), usize::MAX) }.is_empty());
+
+        let slice = vec_f64_to_c_array(Vec::new());
+        assert!(slice.data.is_null());
+        assert_eq!(slice.len, 0);
+        extern_slice_f64_destroy(slice);
+    }
+
+    #[test]
+    fn test_multiple_slices() {
+        for i in 0..100u32 {
+            let slice = ExternSlice::from_vec((0..i).collect());
+            assert_eq!(slice.len, i as usize);
+            destroy_u32_slice(slice);
+        }
+    }
+}
@@ -2,19 +2,458 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ffi::{CStr, c_char, c_int};
 
+/// Borrows a C string as a `&str`, returning an empty string if it is not valid
+/// UTF-8. A single bad byte discards the whole string, so prefer
+/// `c_char_to_string_replaced` unless that is what you want.
+///
+/// `cchar` must be a non-null, NUL-terminated C string that outlives `'a`.
+// This predates the `unsafe fn` helpers below and stays safe to keep existing
+// callers compiling; new helpers taking raw pointers should be `unsafe fn`.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn c_char_to_string<'a>(cchar: *const c_char) -> &'a str {
     let c_str = unsafe { CStr::from_ptr(cchar) };
     c_str.to_str().unwrap_or("")
 }
 
+/// Converts a C string into an owned Rust string, replacing each invalid UTF-8
+/// sequence with `U+FFFD` so the valid content survives. A null pointer yields
+/// an empty string. This is the recommended way to read C strings.
+///
+/// # Safety
+///
+/// `cchar` must be null or point to a valid NUL-terminated C string.
+pub unsafe fn c_char_to_string_replaced(cchar: *const c_char) -> String {
+    if cchar.is_null() {
+        return String::new();
+    }
+    String::from_utf8_lossy(unsafe { CStr::from_ptr(cchar) }.to_bytes()).into_owned()
+}
+
+/// Converts a C string from an untrusted source into a Rust string, scanning at
+/// most `max_len` bytes (including the terminating NUL).
+///
+/// Returns `None` if `cchar` is null or no terminator is found within the bound,
+/// so an unterminated buffer can never trigger an unbounded scan. Invalid UTF-8
+/// is converted lossily.
+///
+/// # Safety
+///
+/// `cchar` must be null or readable up to its terminator or for `max_len` bytes,
+/// whichever comes first.
+pub unsafe fn c_char_to_string_bounded(cchar: *const c_char, max_len: usize) -> Option<String> {
+    if cchar.is_null() {
+        return None;
+    }
+    let bytes = cchar as *const u8;
+    let len = (0..max_len).find(|&i| unsafe { *bytes.add(i) } == 0)? + 1;
+    let bounded = unsafe { core::slice::from_raw_parts(bytes, len) };
+    let c_str = CStr::from_bytes_until_nul(bounded).ok()?;
+    Some(c_str.to_string_lossy().into_owned())
+}
+
+/// Reads a C string without allocating when it is valid UTF-8.
+///
+/// Returns `Cow::Borrowed` for valid UTF-8 and a lossily converted `Cow::Owned`
+/// otherwise, with invalid sequences replaced by `U+FFFD`. A null pointer yields
+/// an empty string. The result borrows from `cchar`, so it cannot outlive the
+/// reference to the pointer; the C string itself must stay alive as long.
+///
+/// # Safety
+///
+/// `*cchar` must be null or point to a valid NUL-terminated C string that
+/// outlives `'a`.
+pub unsafe fn c_char_to_cow<'a>(cchar: &'a *const c_char) -> Cow<'a, str> {
+    if cchar.is_null() {
+        return Cow::Borrowed("");
+    }
+    unsafe { CStr::from_ptr(*cchar) }.to_string_lossy()
+}
+
+/// Converts a Rust string into an owned C string.
+///
+/// A C string cannot contain interior NUL bytes, so the result is truncated at
+/// the first NUL in `r_string`; this is exactly what a C reader would see anyway.
+/// For example `"a\0b"` becomes `"a"`. This function never panics.
+///
+/// The caller must free the returned pointer with `destroy_c_char`.
 pub fn string_to_c_char<T>(r_string: T) -> *mut c_char
 where
     T: Into<String>,
 {
-    CString::new(r_string.into()).unwrap().into_raw()
+    to_c_string(r_string.into()).into_raw()
+}
+
+/// Converts an optional Rust string into a C string, or null for `None`.
+///
+/// The caller must free a non-null result with `destroy_c_char`.
+pub fn opt_string_to_c_char(s: Option<&str>) -> *mut c_char {
+    s.map_or(core::ptr::null_mut(), string_to_c_char)
+}
+
+/// Converts a nullable C string into an owned Rust string, or `None` for null.
+/// Invalid UTF-8 yields an empty string, as with `c_char_to_string`.
+///
+/// # Safety
+///
+/// `cchar` must be null or point to a valid NUL-terminated C string.
+pub unsafe fn c_char_to_opt_string(cchar: *const c_char) -> Option<String> {
+    if cchar.is_null() {
+        None
+    } else {
+        Some(c_char_to_string(cchar).to_owned())
+    }
+}
+
+/// Converts an argv-style array of `len` C strings into owned Rust strings.
+///
+/// A null `ptr` or a `len` too large to be a valid allocation yields an empty
+/// `Vec`, null elements become empty strings and invalid UTF-8 is converted lossily.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` readable pointers, each of which is
+/// null or a valid NUL-terminated C string.
+pub unsafe fn c_string_array_to_vec(ptr: *const *const c_char, len: usize) -> Vec<String> {
+    if ptr.is_null() || crate::memory::checked_layout::<*const c_char>(len).is_none() {
+        return Vec::new();
+    }
+    unsafe { core::slice::from_raw_parts(ptr, len) }
+        .iter()
+        .map(|&element| {
+            if element.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(element) }
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        })
+        .collect()
+}
+
+/// Duplicates a C string into a fresh allocation, like `strdup`, but using
+/// Rust's allocator so the copy can be released with `destroy_c_char`.
+/// Returns null if `src` is null.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("c_char_dup"))]
+pub extern "C" fn c_char_dup(src: *const c_char) -> *mut c_char {
+    if src.is_null() {
+        return core::ptr::null_mut();
+    }
+    unsafe { CStr::from_ptr(src) }.to_owned().into_raw()
+}
+
+/// Checks whether a C string is valid UTF-8 without allocating, so callers can
+/// pick a strict or lossy conversion up front. Returns false for null.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("is_valid_utf8_cstr"))]
+pub extern "C" fn is_valid_utf8_cstr(ptr: *const c_char) -> bool {
+    !ptr.is_null() && unsafe { CStr::from_ptr(ptr) }.to_str().is_ok()
+}
+
+/// Compares two C strings for equality by content. Two nulls are equal and a
+/// null is never equal to a non-null string.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[unsafe(export_name = ffi_symbol!("c_char_eq"))]
+pub extern "C" fn c_char_eq(a: *const c_char, b: *const c_char) -> bool {
+    unsafe { c_char_cmp(a, b) == Ordering::Equal }
+}
+
+/// Orders two C strings by their bytes, which for UTF-8 is the same as
+/// ordering by code point. A null sorts before any non-null string.
+///
+/// # Safety
+///
+/// `a` and `b` must each be null or point to a valid NUL-terminated C string.
+pub unsafe fn c_char_cmp(a: *const c_char, b: *const c_char) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => unsafe { CStr::from_ptr(a).cmp(CStr::from_ptr(b)) },
+    }
+}
+
+/// Converts `s` into a `CString`, truncating at the first interior NUL byte.
+pub(crate) fn to_c_string<T>(s: T) -> CString
+where
+    T: Into<Vec<u8>>,
+{
+    CString::new(s).unwrap_or_else(|e| {
+        let nul_position = e.nul_position();
+        let mut bytes = e.into_vec();
+        bytes.truncate(nul_position);
+        // The bytes now end before the first NUL, so this cannot fail
+        CString::new(bytes).unwrap_or_default()
+    })
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static SCRATCH: core::cell::RefCell<Vec<u8>> = const { core::cell::RefCell::new(Vec::new()) };
+}
+
+/// Converts `s` into a C string in a reusable per-thread buffer, for hot paths
+/// where allocating a fresh string with `string_to_c_char` on every call is too
+/// slow. Like `string_to_c_char`, the result is truncated at the first NUL byte.
+///
+/// The returned pointer is borrowed, not owned: it stays valid only until the
+/// next call to this function on the same thread, which overwrites and may move
+/// the buffer, or until the thread exits. C must copy the string if it needs it
+/// any longer, and must never free it.
+#[cfg(feature = "std")]
+pub fn string_to_c_char_scratch(s: &str) -> *const c_char {
+    let bytes = s.as_bytes();
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    SCRATCH.with_borrow_mut(|buf| {
+        buf.clear();
+        buf.extend_from_slice(&bytes[..len]);
+        buf.push(0);
+        buf.as_ptr() as *const c_char
+    })
+}
+
+/// Converts a path into an owned C string without losing non-UTF-8 paths.
+///
+/// On Unix the raw `OsStr` bytes are passed through unchanged; elsewhere the path
+/// is converted to UTF-8 lossily. Like `string_to_c_char`, the result is truncated
+/// at the first NUL byte. The caller must free it with `destroy_c_char`.
+#[cfg(feature = "std")]
+pub fn path_to_c_char(p: &std::path::Path) -> *mut c_char {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(p.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = p.to_string_lossy().into_owned().into_bytes();
+    to_c_string(bytes).into_raw()
+}
+
+/// Converts a C string into a path, the reverse of `path_to_c_char`.
+///
+/// On Unix the bytes are used as-is; elsewhere invalid UTF-8 is replaced with
+/// `U+FFFD`. A null pointer yields an empty path.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid NUL-terminated C string.
+#[cfg(feature = "std")]
+pub unsafe fn c_char_to_path(ptr: *const c_char) -> std::path::PathBuf {
+    if ptr.is_null() {
+        return std::path::PathBuf::new();
+    }
+    let bytes = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+    #[cfg(unix)]
+    let path = <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes).into();
+    #[cfg(not(unix))]
+    let path = String::from_utf8_lossy(bytes).into_owned().into();
+    path
+}
+
+/// Converts `s` into a C string like `string_to_c_char`, but only if it is pure
+/// ASCII, for protocols that require ASCII identifiers.
+///
+/// Fails with an `ErrorCode::ValidationError` naming the offending byte offset if
+/// `s` contains a byte of 0x80 or above or a NUL, instead of emitting multibyte
+/// sequences or truncating. The caller must free the result with `destroy_c_char`.
+pub fn ascii_string_to_c_char(s: &str) -> Result<*mut c_char, crate::result::OwnedExternError> {
+    match s.bytes().position(|b| !b.is_ascii() || b == 0) {
+        Some(offset) => Err(crate::result::OwnedExternError {
+            code: crate::result::ErrorCode::ValidationError,
+            message: alloc::format!("Non-ASCII or NUL byte at offset {}", offset),
+            detail: None,
+        }),
+        None => Ok(string_to_c_char(s)),
+    }
+}
+
+/// Converts `s` into a C string like `string_to_c_char`, additionally writing its
+/// byte length (excluding the terminating NUL) through `out_len` so callers don't
+/// need a separate `strlen`. A null `out_len` is ignored. The length is that of
+/// the returned string, i.e. up to any interior NUL.
+///
+/// # Safety
+///
+/// `out_len` must be null or valid for writing a `usize`.
+pub unsafe fn string_to_c_char_with_len(s: &str, out_len: *mut usize) -> *mut c_char {
+    let c_string = to_c_string(s);
+    if !out_len.is_null() {
+        unsafe { *out_len = c_string.as_bytes().len() };
+    }
+    c_string.into_raw()
+}
+
+/// The longest string in bytes whose length fits in a C `int`, the usual `max` for
+/// `string_to_c_char_bounded`.
+pub const C_INT_MAX_LEN: usize = c_int::MAX as usize;
+
+/// Converts `s` into a C string like `string_to_c_char`, for legacy C APIs that
+/// cannot take a length above `max`, such as one passed as an `int`.
+///
+/// Returns an `ErrorCode::InvalidArgumentError` result if the string C would see,
+/// i.e. up to any interior NUL, is longer than `max` bytes, rather than handing
+/// back a length C misreads as negative. On success `ok` holds the C string, to be
+/// freed with `destroy_c_char` before the result itself.
+pub fn string_to_c_char_bounded(s: &str, max: usize) -> *mut crate::result::ExternResult {
+    let c_string = to_c_string(s);
+    let len = c_string.as_bytes().len();
+    if len > max {
+        return crate::result::ExternResult::err(
+            crate::result::ErrorCode::InvalidArgumentError,
+            alloc::format!("String of {} bytes exceeds the maximum of {}", len, max),
+        );
+    }
+    crate::result::ExternResult::ok_ptr(c_string.into_raw())
+}
+
+/// Converts `s` into a C string like `string_to_c_char`, but rejects an interior
+/// NUL instead of truncating at it, so no part of the string is silently lost.
+///
+/// Returns an `ErrorCode::ValidationError` result naming the position of the first
+/// NUL. On success `ok` holds the C string, to be freed with `destroy_c_char`
+/// before the result itself.
+pub fn try_string_to_c_char(s: &str) -> *mut crate::result::ExternResult {
+    match CString::new(s) {
+        Ok(c_string) => crate::result::ExternResult::ok_ptr(c_string.into_raw()),
+        Err(e) => crate::result::ExternResult::err(
+            crate::result::ErrorCode::ValidationError,
+            alloc::format!(
+                "String contains a NUL byte at position {}",
+                e.nul_position()
+            ),
+        ),
+    }
+}
+
+/// Reads a C string strictly, for boundaries where invalid UTF-8 must be reported
+/// rather than replaced or discarded.
+///
+/// On success `ok` holds a boxed `String`; free the result with
+/// `extern_result_into_result::<String>` or a `define_result_destructor!`
+/// destructor. Invalid UTF-8 yields an `ErrorCode::ValidationError` result naming
+/// the offset of the first bad byte, and a null `ptr` an
+/// `ErrorCode::InvalidArgumentError` result.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid NUL-terminated C string.
+pub unsafe fn c_char_to_string_result(ptr: *const c_char) -> *mut crate::result::ExternResult {
+    if ptr.is_null() {
+        return crate::result::ExternResult::err(
+            crate::result::ErrorCode::InvalidArgumentError,
+            "String is null",
+        );
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => crate::result::ExternResult::ok(String::from(s)),
+        Err(e) => crate::result::ExternResult::err(
+            crate::result::ErrorCode::ValidationError,
+            alloc::format!("Invalid UTF-8 at byte offset {}", e.valid_up_to()),
+        ),
+    }
+}
+
+/// Converts a Rust `bool` into the C convention of `1` for true and `0` for false.
+pub fn bool_to_c_int(b: bool) -> c_int {
+    c_int::from(b)
+}
+
+/// Converts a C boolean `int` into a Rust `bool`: any nonzero value is true.
+pub fn c_int_to_bool(v: c_int) -> bool {
+    v != 0
+}
+
+/// Converts a tri-state C `int` into an `Option<bool>`: negative values mean
+/// "unknown" and map to `None`, `0` is false and positive values are true.
+pub fn c_int_to_option_bool(v: c_int) -> Option<bool> {
+    if v < 0 { None } else { Some(v != 0) }
+}
+
+/// Converts a UTF-16 buffer of `len` code units, which need not be
+/// NUL-terminated, into a Rust string. Unpaired surrogates are replaced with
+/// `U+FFFD`. A null `ptr` or a `len` too large to be a valid allocation yields an
+/// empty string.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` readable `u16` code units.
+pub unsafe fn utf16_buf_to_string(ptr: *const u16, len: usize) -> String {
+    if ptr.is_null() || crate::memory::checked_layout::<u16>(len).is_none() {
+        return String::new();
+    }
+    String::from_utf16_lossy(unsafe { core::slice::from_raw_parts(ptr, len) })
+}
+
+/// Converts `s` into a UTF-16 buffer without a terminating NUL, writing its
+/// length in code units through `out_len`. A null `out_len` is ignored.
+///
+/// An empty string yields null. The caller must free a non-null result with
+/// `destroy_utf16_buf`, passing the same length.
+///
+/// # Safety
+///
+/// `out_len` must be null or valid for writing a `usize`.
+pub unsafe fn string_to_utf16_buf(s: &str, out_len: *mut usize) -> *mut u16 {
+    let buf: Box<[u16]> = s.encode_utf16().collect();
+    if let Some(out_len) = unsafe { out_len.as_mut() } {
+        *out_len = buf.len();
+    }
+    if buf.is_empty() {
+        return core::ptr::null_mut();
+    }
+    Box::into_raw(buf) as *mut u16
+}
+
+/// Frees a buffer of `len` code units created by `string_to_utf16_buf`.
+/// Null is ignored.
+#[unsafe(export_name = ffi_symbol!("destroy_utf16_buf"))]
+pub extern "C" fn destroy_utf16_buf(ptr: *mut u16, len: usize) {
+    if !ptr.is_null() {
+        let _ = unsafe { Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len)) };
+    }
+}
+
+/// An owned C string handed to C together with its length in bytes, e.g. for
+/// bindings that need both at once. It is the string counterpart of
+/// `ExternBuffer`: `data` is NUL-terminated for plain C, and `len` counts the
+/// bytes before the terminator so other languages need not scan for it.
+///
+/// #Safety
+///
+/// Callers are responsible for managing the memory for the return value.
+/// A destructor `extern_string_destroy` is provided for releasing the memory for this type.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ExternString {
+    pub data: *mut c_char,
+    pub len: usize,
+}
+
+impl From<String> for ExternString {
+    /// Like `string_to_c_char`, the string is truncated at the first interior NUL,
+    /// and `len` is the length of what remains.
+    fn from(s: String) -> Self {
+        let c_string = to_c_string(s);
+        let len = c_string.as_bytes().len();
+        ExternString {
+            data: c_string.into_raw(),
+            len,
+        }
+    }
+}
+
+/// Releases an `ExternString` created by this crate. A null `data` is ignored.
+#[unsafe(export_name = ffi_symbol!("extern_string_destroy"))]
+pub extern "C" fn extern_string_destroy(s: ExternString) {
+    if !s.data.is_null() {
+        let _ = unsafe { CString::from_raw(s.data) };
+    }
 }
 
 #[cfg(test)]
@@ -214,6 +653,585 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_string_to_c_char_with_len_ascii() {
+        let rust_str = "Hello";
+        let mut len = 0usize;
+        let c_str_ptr = unsafe { string_to_c_char_with_len(rust_str, &mut len) };
+
+        assert_eq!(len, rust_str.len());
+        assert_eq!(c_char_to_string(c_str_ptr), rust_str);
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_string_to_c_char_with_len_multibyte() {
+        // Length is reported in bytes, not characters
+        let rust_str = "世界 🦀";
+        let mut len = 0usize;
+        let c_str_ptr = unsafe { string_to_c_char_with_len(rust_str, &mut len) };
+
+        assert_eq!(len, rust_str.len());
+        assert_eq!(len, 11);
+        assert_eq!(c_char_to_string(c_str_ptr), rust_str);
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_string_to_c_char_with_len_interior_nul() {
+        // The length matches the truncated string C sees, not the input
+        let mut len = 0usize;
+        let c_str_ptr = unsafe { string_to_c_char_with_len("a\0b", &mut len) };
+
+        assert_eq!(len, 1);
+        assert_eq!(c_char_to_string(c_str_ptr), "a");
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_string_to_c_char_with_len_null_out_param() {
+        let c_str_ptr =
+            unsafe { string_to_c_char_with_len("No length wanted", std::ptr::null_mut()) };
+
+        assert!(!c_str_ptr.is_null());
+        assert_eq!(c_char_to_string(c_str_ptr), "No length wanted");
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_string_to_c_char_bounded_at_limit() {
+        let result = string_to_c_char_bounded("hello", 5);
+
+        assert!(crate::result::extern_result_is_ok(result));
+        let c_str = crate::result::extern_result_get_ok(result) as *mut c_char;
+        assert_eq!(c_char_to_string(c_str), "hello");
+        crate::memory::destroy_c_char(c_str);
+        crate::result::extern_result_destroy(result);
+    }
+
+    #[test]
+    fn test_string_to_c_char_bounded_over_limit() {
+        let result = string_to_c_char_bounded("hello!", 5);
+
+        assert!(!crate::result::extern_result_is_ok(result));
+        let error = unsafe { &*crate::result::extern_result_get_err(result) };
+        assert_eq!(error.code(), crate::result::ErrorCode::InvalidArgumentError);
+        assert_eq!(
+            error.message(),
+            "String of 6 bytes exceeds the maximum of 5"
+        );
+        crate::result::extern_result_destroy(result);
+    }
+
+    #[test]
+    fn test_string_to_c_char_bounded_measures_bytes() {
+        // Two characters, but six bytes
+        let result = string_to_c_char_bounded("世界", 5);
+        assert!(!crate::result::extern_result_is_ok(result));
+        crate::result::extern_result_destroy(result);
+
+        // Only the part before an interior NUL reaches C
+        let result = string_to_c_char_bounded("hello\0world", 5);
+        assert!(crate::result::extern_result_is_ok(result));
+        crate::memory::destroy_c_char(crate::result::extern_result_get_ok(result) as *mut c_char);
+        crate::result::extern_result_destroy(result);
+    }
+
+    #[test]
+    fn test_c_char_to_string_result_valid() {
+        let input = CString::new("Hello 世界").unwrap();
+
+        let result = unsafe { c_char_to_string_result(input.as_ptr()) };
+
+        let value = unsafe { crate::result::extern_result_into_result::<String>(result) };
+        assert_eq!(*value.unwrap().unwrap(), "Hello 世界");
+    }
+
+    #[test]
+    fn test_c_char_to_string_result_invalid_utf8() {
+        let input = CString::new(b"ok\xff\xfe".to_vec()).unwrap();
+
+        let result = unsafe { c_char_to_string_result(input.as_ptr()) };
+
+        let error =
+            unsafe { crate::result::extern_result_into_result::<String>(result) }.unwrap_err();
+        assert_eq!(error.code, crate::result::ErrorCode::ValidationError);
+        assert_eq!(error.message, "Invalid UTF-8 at byte offset 2");
+    }
+
+    #[test]
+    fn test_c_char_to_string_result_null() {
+        let result = unsafe { c_char_to_string_result(std::ptr::null()) };
+
+        let error =
+            unsafe { crate::result::extern_result_into_result::<String>(result) }.unwrap_err();
+        assert_eq!(error.code, crate::result::ErrorCode::InvalidArgumentError);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_string_to_c_char_scratch_reuses_buffer() {
+        let first = string_to_c_char_scratch("first value");
+        assert_eq!(unsafe { CStr::from_ptr(first) }, c"first value");
+
+        let second = string_to_c_char_scratch("second");
+
+        // The same allocation is reused, so the first string is gone
+        assert_eq!(first, second);
+        assert_eq!(unsafe { CStr::from_ptr(first) }, c"second");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_string_to_c_char_scratch_truncates_and_is_per_thread() {
+        let main = string_to_c_char_scratch("a\0b");
+        assert_eq!(unsafe { CStr::from_ptr(main) }, c"a");
+
+        let other = std::thread::spawn(|| string_to_c_char_scratch("other") as usize)
+            .join()
+            .unwrap();
+        assert_ne!(other, main as usize);
+        assert_eq!(unsafe { CStr::from_ptr(main) }, c"a");
+    }
+
+    #[test]
+    fn test_try_string_to_c_char_clean() {
+        let result = try_string_to_c_char("Hello 世界");
+
+        assert!(crate::result::extern_result_is_ok(result));
+        let c_str = crate::result::extern_result_get_ok(result) as *mut c_char;
+        assert_eq!(c_char_to_string(c_str), "Hello 世界");
+        crate::memory::destroy_c_char(c_str);
+        crate::result::extern_result_destroy(result);
+    }
+
+    #[test]
+    fn test_try_string_to_c_char_interior_nul() {
+        let result = try_string_to_c_char("key\0value");
+
+        let owned = unsafe { crate::result::OwnedExternResult::from_raw(result) };
+        let error = owned.error().unwrap();
+        assert_eq!(error.code(), crate::result::ErrorCode::ValidationError);
+        assert_eq!(error.message(), "String contains a NUL byte at position 3");
+        assert!(owned.as_ref().ok.is_null());
+    }
+
+    #[test]
+    fn test_string_to_c_char_interior_nul_truncates() {
+        // Regression test: this used to panic inside `CString::new`
+        let c_str_ptr = string_to_c_char("a\0b");
+
+        assert_eq!(c_char_to_string(c_str_ptr), "a");
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_string_to_c_char_leading_nul_is_empty() {
+        let c_str_ptr = string_to_c_char(String::from("\0hidden"));
+
+        assert_eq!(c_char_to_string(c_str_ptr), "");
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_c_char_to_cow_borrows_valid_utf8() {
+        let c_str = CString::new("Borrowed 世界").unwrap();
+        let c_str_ptr = c_str.as_ptr();
+
+        let result = unsafe { c_char_to_cow(&c_str_ptr) };
+
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(result, "Borrowed 世界");
+    }
+
+    #[test]
+    fn test_c_char_to_cow_owns_invalid_utf8() {
+        static INVALID_UTF8: [u8; 5] = [b'a', 0xFF, b'b', 0xFE, 0x00];
+        let c_str_ptr = INVALID_UTF8.as_ptr() as *const c_char;
+
+        let result = unsafe { c_char_to_cow(&c_str_ptr) };
+
+        assert!(matches!(result, Cow::Owned(_)));
+        assert_eq!(result, "a\u{FFFD}b\u{FFFD}");
+    }
+
+    #[test]
+    fn test_c_char_to_cow_null() {
+        let null_ptr: *const c_char = std::ptr::null();
+
+        assert_eq!(unsafe { c_char_to_cow(&null_ptr) }, "");
+    }
+
+    #[test]
+    fn test_opt_string_to_c_char_some() {
+        let c_str_ptr = opt_string_to_c_char(Some("Present"));
+
+        assert!(!c_str_ptr.is_null());
+        assert_eq!(c_char_to_string(c_str_ptr), "Present");
+
+        unsafe {
+            let _ = CString::from_raw(c_str_ptr);
+        }
+    }
+
+    #[test]
+    fn test_opt_string_to_c_char_none() {
+        assert!(opt_string_to_c_char(None).is_null());
+    }
+
+    #[test]
+    fn test_c_char_to_opt_string_some() {
+        let c_str = CString::new("Present 🦀").unwrap();
+
+        assert_eq!(
+            unsafe { c_char_to_opt_string(c_str.as_ptr()) },
+            Some(String::from("Present 🦀"))
+        );
+    }
+
+    #[test]
+    fn test_c_char_to_opt_string_none() {
+        assert_eq!(unsafe { c_char_to_opt_string(std::ptr::null()) }, None);
+    }
+
+    #[test]
+    fn test_opt_string_round_trip() {
+        for original in [Some("Round trip"), Some(""), None] {
+            let c_str_ptr = opt_string_to_c_char(original);
+            assert_eq!(
+                unsafe { c_char_to_opt_string(c_str_ptr) }.as_deref(),
+                original
+            );
+
+            if !c_str_ptr.is_null() {
+                unsafe {
+                    let _ = CString::from_raw(c_str_ptr);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_c_char_to_string_bounded_within_bound() {
+        let c_str = CString::new("Bounded").unwrap();
+
+        // 7 characters plus the terminator fit exactly
+        assert_eq!(
+            unsafe { c_char_to_string_bounded(c_str.as_ptr(), 8) },
+            Some(String::from("Bounded"))
+        );
+        assert_eq!(
+            unsafe { c_char_to_string_bounded(c_str.as_ptr(), 1024) },
+            Some(String::from("Bounded"))
+        );
+    }
+
+    #[test]
+    fn test_c_char_to_string_bounded_unterminated() {
+        // No terminator anywhere in the buffer
+        let buffer = [b'x'; 16];
+        let c_str_ptr = buffer.as_ptr() as *const c_char;
+
+        assert_eq!(
+            unsafe { c_char_to_string_bounded(c_str_ptr, buffer.len()) },
+            None
+        );
+    }
+
+    #[test]
+    fn test_c_char_to_string_bounded_terminator_past_bound() {
+        let c_str = CString::new("Too long").unwrap();
+
+        assert_eq!(unsafe { c_char_to_string_bounded(c_str.as_ptr(), 8) }, None);
+        assert_eq!(unsafe { c_char_to_string_bounded(c_str.as_ptr(), 0) }, None);
+    }
+
+    #[test]
+    fn test_c_char_to_string_bounded_null() {
+        assert_eq!(
+            unsafe { c_char_to_string_bounded(std::ptr::null(), 16) },
+            None
+        );
+    }
+
+    #[test]
+    fn test_bool_to_c_int() {
+        assert_eq!(bool_to_c_int(true), 1);
+        assert_eq!(bool_to_c_int(false), 0);
+    }
+
+    #[test]
+    fn test_c_int_to_bool() {
+        assert!(!c_int_to_bool(0));
+        assert!(c_int_to_bool(1));
+        assert!(c_int_to_bool(-1));
+        assert!(c_int_to_bool(c_int::MAX));
+    }
+
+    #[test]
+    fn test_c_int_to_option_bool() {
+        assert_eq!(c_int_to_option_bool(0), Some(false));
+        assert_eq!(c_int_to_option_bool(1), Some(true));
+        assert_eq!(c_int_to_option_bool(-1), None);
+        assert_eq!(c_int_to_option_bool(c_int::MIN), None);
+        assert_eq!(c_int_to_option_bool(c_int::MAX), Some(true));
+    }
+
+    #[test]
+    fn test_bool_c_int_round_trip() {
+        for b in [true, false] {
+            assert_eq!(c_int_to_bool(bool_to_c_int(b)), b);
+            assert_eq!(c_int_to_option_bool(bool_to_c_int(b)), Some(b));
+        }
+    }
+
+    #[test]
+    fn test_c_string_array_to_vec() {
+        let args: Vec<CString> = ["program", "--flag", "值"]
+            .iter()
+            .map(|s| CString::new(*s).unwrap())
+            .collect();
+        let argv: Vec<*const c_char> = args.iter().map(|s| s.as_ptr()).collect();
+
+        let result = unsafe { c_string_array_to_vec(argv.as_ptr(), argv.len()) };
+
+        assert_eq!(result, vec!["program", "--flag", "值"]);
+    }
+
+    #[test]
+    fn test_c_string_array_to_vec_null_element() {
+        let first = CString::new("first").unwrap();
+        let last = CString::new("last").unwrap();
+        let argv = [first.as_ptr(), std::ptr::null(), last.as_ptr()];
+
+        let result = unsafe { c_string_array_to_vec(argv.as_ptr(), argv.len()) };
+
+        assert_eq!(result, vec!["first", "", "last"]);
+    }
+
+    #[test]
+    fn test_c_string_array_to_vec_null_array() {
+        assert!(unsafe { c_string_array_to_vec(std::ptr::null(), 3) }.is_empty());
+    }
+
+    #[test]
+    fn test_absurd_lengths_fail_gracefully() {
+        // The lengths are rejected before the dangling pointers are ever read
+        let strings = std::ptr::NonNull::<*const c_char>::dangling().as_ptr();
+        let units = std::ptr::NonNull::<u16>::dangling().as_ptr();
+
+        assert!(unsafe { c_string_array_to_vec(strings, usize::MAX) }.is_empty());
+        assert_eq!(
+            unsafe { utf16_buf_to_string(units, usize::MAX / 2 + 1) },
+            ""
+        );
+    }
+
+    #[test]
+    fn test_c_char_to_string_replaced_keeps_valid_content() {
+        let mostly_valid = CString::new(b"Hello \xff world".to_vec()).unwrap();
+
+        assert_eq!(c_char_to_string(mostly_valid.as_ptr()), "");
+        assert_eq!(
+            unsafe { c_char_to_string_replaced(mostly_valid.as_ptr()) },
+            "Hello \u{FFFD} world"
+        );
+    }
+
+    #[test]
+    fn test_c_char_to_string_replaced_valid_and_null() {
+        let valid = CString::new("世界 🦀").unwrap();
+
+        assert_eq!(
+            unsafe { c_char_to_string_replaced(valid.as_ptr()) },
+            "世界 🦀"
+        );
+        assert_eq!(unsafe { c_char_to_string_replaced(std::ptr::null()) }, "");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_path_round_trip() {
+        let path = std::path::Path::new("/tmp/世界/report.txt");
+        let c_path = path_to_c_char(path);
+
+        assert_eq!(c_char_to_string(c_path), "/tmp/世界/report.txt");
+        assert_eq!(unsafe { c_char_to_path(c_path) }, path);
+        crate::memory::destroy_c_char(c_path);
+        assert_eq!(
+            unsafe { c_char_to_path(std::ptr::null()) },
+            std::path::PathBuf::new()
+        );
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    #[test]
+    fn test_path_non_utf8_preserved() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::path::Path::new(OsStr::from_bytes(b"/data/caf\xe9.txt"));
+        let c_path = path_to_c_char(path);
+
+        assert_eq!(
+            unsafe { CStr::from_ptr(c_path) }.to_bytes(),
+            b"/data/caf\xe9.txt"
+        );
+        assert_eq!(
+            unsafe { c_char_to_path(c_path) }.as_os_str().as_bytes(),
+            b"/data/caf\xe9.txt"
+        );
+        crate::memory::destroy_c_char(c_path);
+    }
+
+    #[test]
+    fn test_ascii_string_to_c_char() {
+        let c_str = ascii_string_to_c_char("user_id-42").unwrap();
+
+        assert_eq!(c_char_to_string(c_str), "user_id-42");
+        crate::memory::destroy_c_char(c_str);
+    }
+
+    #[test]
+    fn test_ascii_string_to_c_char_rejects_multibyte_and_nul() {
+        let error = ascii_string_to_c_char("café").unwrap_err();
+        assert_eq!(error.code, crate::result::ErrorCode::ValidationError);
+        assert_eq!(error.message, "Non-ASCII or NUL byte at offset 3");
+
+        let error = ascii_string_to_c_char("a\0b").unwrap_err();
+        assert_eq!(error.message, "Non-ASCII or NUL byte at offset 1");
+    }
+
+    #[test]
+    fn test_is_valid_utf8_cstr_valid() {
+        let ascii = CString::new("plain").unwrap();
+        let unicode = CString::new("世界 🦀").unwrap();
+
+        assert!(is_valid_utf8_cstr(ascii.as_ptr()));
+        assert!(is_valid_utf8_cstr(unicode.as_ptr()));
+        assert!(is_valid_utf8_cstr(c"".as_ptr()));
+    }
+
+    #[test]
+    fn test_is_valid_utf8_cstr_invalid() {
+        // A lone continuation byte and a truncated multi-byte sequence
+        let stray = CString::new(vec![b'a', 0x80, b'b']).unwrap();
+        let truncated = CString::new(vec![0xe4, 0xb8]).unwrap();
+
+        assert!(!is_valid_utf8_cstr(stray.as_ptr()));
+        assert!(!is_valid_utf8_cstr(truncated.as_ptr()));
+    }
+
+    #[test]
+    fn test_is_valid_utf8_cstr_null() {
+        assert!(!is_valid_utf8_cstr(std::ptr::null()));
+    }
+
+    #[test]
+    fn test_c_char_eq_nulls() {
+        let value = CString::new("value").unwrap();
+
+        assert!(c_char_eq(std::ptr::null(), std::ptr::null()));
+        assert!(!c_char_eq(std::ptr::null(), value.as_ptr()));
+        assert!(!c_char_eq(value.as_ptr(), std::ptr::null()));
+    }
+
+    #[test]
+    fn test_c_char_eq_contents() {
+        let a = CString::new("世界 🦀").unwrap();
+        let b = CString::new("世界 🦀").unwrap();
+        let c = CString::new("世界 🐍").unwrap();
+
+        // Equal contents at different addresses
+        assert!(c_char_eq(a.as_ptr(), b.as_ptr()));
+        assert!(!c_char_eq(a.as_ptr(), c.as_ptr()));
+        assert!(!c_char_eq(
+            a.as_ptr(),
+            CString::new("世界").unwrap().as_ptr()
+        ));
+    }
+
+    #[test]
+    fn test_c_char_cmp() {
+        let apple = CString::new("apple").unwrap();
+        let banana = CString::new("banana").unwrap();
+        let accented = CString::new("é").unwrap();
+
+        assert_eq!(
+            unsafe { c_char_cmp(apple.as_ptr(), banana.as_ptr()) },
+            Ordering::Less
+        );
+        assert_eq!(
+            unsafe { c_char_cmp(banana.as_ptr(), apple.as_ptr()) },
+            Ordering::Greater
+        );
+        assert_eq!(
+            unsafe { c_char_cmp(apple.as_ptr(), apple.as_ptr()) },
+            Ordering::Equal
+        );
+        assert_eq!(
+            unsafe { c_char_cmp(banana.as_ptr(), accented.as_ptr()) },
+            "banana".cmp("é")
+        );
+        assert_eq!(
+            unsafe { c_char_cmp(std::ptr::null(), apple.as_ptr()) },
+            Ordering::Less
+        );
+        assert_eq!(
+            unsafe { c_char_cmp(std::ptr::null(), std::ptr::null()) },
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_c_char_dup_unicode() {
+        let original = CString::new("Duplicate 世界 🦀").unwrap();
+        let copy = c_char_dup(original.as_ptr());
+
+        assert!(!copy.is_null());
+        assert_ne!(copy as *const c_char, original.as_ptr());
+        assert_eq!(c_char_to_string(copy), "Duplicate 世界 🦀");
+
+        crate::memory::destroy_c_char(copy);
+    }
+
+    #[test]
+    fn test_c_char_dup_is_independent() {
+        let source = string_to_c_char("Source");
+        let copy = c_char_dup(source);
+
+        // Mutating and freeing the source leaves the copy untouched
+        unsafe { *source = b'X' as c_char };
+        assert_eq!(c_char_to_string(source), "Xource");
+        crate::memory::destroy_c_char(source);
+
+        assert_eq!(c_char_to_string(copy), "Source");
+        crate::memory::destroy_c_char(copy);
+    }
+
+    #[test]
+    fn test_c_char_dup_null() {
+        assert!(c_char_dup(std::ptr::null()).is_null());
+    }
+
     #[test]
     fn test_string_with_embedded_quotes() {
         // Test strings with various quote characters
@@ -227,4 +1245,76 @@ mod tests {
             let _ = CString::from_raw(c_str_ptr);
         }
     }
+
+    #[test]
+    fn test_utf16_buf_round_trip() {
+        // "😀" is a surrogate pair in UTF-16
+        let original = "héllo 😀";
+        let mut len = 0;
+        let buf = unsafe { string_to_utf16_buf(original, &mut len) };
+
+        assert_eq!(len, 8);
+        assert_eq!(unsafe { utf16_buf_to_string(buf, len) }, original);
+        destroy_utf16_buf(buf, len);
+    }
+
+    #[test]
+    fn test_utf16_buf_empty() {
+        let mut len = 1;
+        let buf = unsafe { string_to_utf16_buf("", &mut len) };
+
+        assert!(buf.is_null());
+        assert_eq!(len, 0);
+        assert_eq!(unsafe { utf16_buf_to_string(buf, len) }, "");
+        destroy_utf16_buf(buf, len);
+    }
+
+    #[test]
+    fn test_utf16_buf_lone_surrogate() {
+        // A high surrogate not followed by a low surrogate
+        let units = [0x0061, 0xD83D, 0x0062];
+        assert_eq!(
+            unsafe { utf16_buf_to_string(units.as_ptr(), units.len()) },
+            "a\u{FFFD}b"
+        );
+
+        // Only `len` units are read; there is no terminator
+        assert_eq!(unsafe { utf16_buf_to_string(units.as_ptr(), 1) }, "a");
+    }
+
+    #[test]
+    fn test_extern_string_ascii() {
+        let s = ExternString::from(String::from("hello"));
+
+        assert_eq!(s.len, 5);
+        let bytes = unsafe { std::slice::from_raw_parts(s.data as *const u8, s.len + 1) };
+        assert_eq!(bytes, b"hello\0");
+        extern_string_destroy(s);
+    }
+
+    #[test]
+    fn test_extern_string_multibyte() {
+        let s = ExternString::from(String::from("Grüße 世界"));
+
+        // `len` counts bytes, not characters
+        assert_eq!(s.len, "Grüße 世界".len());
+        assert_eq!(s.len, 14);
+        let bytes = unsafe { std::slice::from_raw_parts(s.data as *const u8, s.len + 1) };
+        assert_eq!(&bytes[..s.len], "Grüße 世界".as_bytes());
+        assert_eq!(bytes[s.len], 0);
+        assert_eq!(unsafe { CStr::from_ptr(s.data) }.to_bytes().len(), s.len);
+        extern_string_destroy(s);
+    }
+
+    #[test]
+    fn test_extern_string_interior_nul_and_empty() {
+        let s = ExternString::from(String::from("a\0b"));
+        assert_eq!(s.len, 1);
+        extern_string_destroy(s);
+
+        let s = ExternString::from(String::new());
+        assert!(!s.data.is_null());
+        assert_eq!(s.len, 0);
+        extern_string_destroy(s);
+    }
 }
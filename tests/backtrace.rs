@@ -0,0 +1,33 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Enabling capture means setting `RUST_BACKTRACE`, which applies to the whole
+// process, so this lives in its own test binary with a single test.
+
+use ffi_toolkit::result::{
+    ErrorCode, ExternErrorBuilder, extern_error_clone, extern_error_get_backtrace,
+    free_extern_error,
+};
+
+#[test]
+fn test_backtrace_captured_when_enabled() {
+    // SAFETY: this is the only test in the binary, so no other thread is
+    // reading the environment while it is written.
+    unsafe { std::env::set_var("RUST_BACKTRACE", "1") };
+    let error = ExternErrorBuilder::new(ErrorCode::Other)
+        .message("Deep failure")
+        .build();
+
+    assert!(!extern_error_get_backtrace(error).is_null());
+    assert!(!unsafe { &*error }.backtrace().unwrap().is_empty());
+
+    let clone = extern_error_clone(error);
+    assert_eq!(
+        unsafe { &*clone }.backtrace(),
+        unsafe { &*error }.backtrace()
+    );
+    free_extern_error(error);
+    free_extern_error(clone);
+    assert!(extern_error_get_backtrace(std::ptr::null()).is_null());
+}
@@ -0,0 +1,71 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// The default error code is process-wide, so this lives in its own test binary
+// where changing it cannot affect conversions in other tests.
+
+use std::fmt;
+
+use ffi_toolkit::result::{
+    ErrorCode, ExternResult, ExternResultF64, OwnedExternResult, default_error_code,
+    free_extern_error, set_default_error_code,
+};
+
+#[derive(Debug)]
+struct DiskError;
+
+impl fmt::Display for DiskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Disk unavailable")
+    }
+}
+
+impl std::error::Error for DiskError {}
+
+fn error_code(result: *mut ExternResult) -> ErrorCode {
+    let owned = unsafe { OwnedExternResult::from_raw(result) };
+    owned.error().unwrap().code()
+}
+
+fn convert_error() -> ErrorCode {
+    error_code(ExternResult::from(Err::<u8, _>(DiskError)).into_boxed_raw())
+}
+
+#[test]
+fn test_default_error_code_is_used_by_from() {
+    assert_eq!(default_error_code(), ErrorCode::Other);
+    assert_eq!(convert_error(), ErrorCode::Other);
+
+    set_default_error_code(ErrorCode::IoError);
+    assert_eq!(default_error_code(), ErrorCode::IoError);
+    assert_eq!(convert_error(), ErrorCode::IoError);
+
+    // Other threads see the same setting
+    let code = std::thread::spawn(convert_error).join().unwrap();
+    assert_eq!(code, ErrorCode::IoError);
+
+    // So do the other conversions of arbitrary errors
+    let unit = ExternResult::from_unit_result(Err(DiskError));
+    assert_eq!(error_code(unit), ErrorCode::IoError);
+    let bytes = ExternResult::from_bytes_result(Err(DiskError));
+    assert_eq!(error_code(bytes), ErrorCode::IoError);
+    let scalar = ExternResultF64::from(Err(DiskError));
+    assert!(scalar.is_err);
+    assert_eq!(unsafe { &*scalar.err }.code(), ErrorCode::IoError);
+    free_extern_error(scalar.err as *mut _);
+    #[cfg(feature = "std")]
+    {
+        let future = std::future::ready(Err::<u8, _>(DiskError));
+        let blocked = ffi_toolkit::future::block_on_to_result(future);
+        assert_eq!(error_code(blocked), ErrorCode::IoError);
+    }
+    #[cfg(feature = "anyhow")]
+    {
+        let anyhow = ExternResult::from_anyhow(anyhow::anyhow!("Disk unavailable"));
+        assert_eq!(error_code(anyhow), ErrorCode::IoError);
+    }
+
+    set_default_error_code(ErrorCode::Other);
+    assert_eq!(convert_error(), ErrorCode::Other);
+}
@@ -0,0 +1,47 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// The live allocation count is process-wide, so this lives in its own test
+// binary where no other test creates results concurrently.
+
+use ffi_toolkit::memory::ffi_live_result_count;
+use ffi_toolkit::result::{
+    ErrorCode, ExternResult, OwnedExternResult, combine_results, extern_result_destroy,
+    extern_result_into_result,
+};
+
+#[test]
+fn test_live_result_count_returns_to_zero() {
+    assert_eq!(ffi_live_result_count(), 0);
+
+    let ok = ExternResult::ok(42u32);
+    let null = ExternResult::ok_null();
+    let err = ExternResult::err(ErrorCode::NotFoundError, "Missing");
+    let converted =
+        ExternResult::from(Ok::<_, std::io::Error>(String::from("value"))).into_boxed_raw();
+
+    let expected = if cfg!(debug_assertions) { 4 } else { 0 };
+    assert_eq!(ffi_live_result_count(), expected);
+
+    unsafe {
+        let _ = extern_result_into_result::<u32>(ok);
+        let _ = extern_result_into_result::<String>(converted);
+    }
+    extern_result_destroy(null);
+    drop(unsafe { OwnedExternResult::from_raw(err) });
+
+    assert_eq!(ffi_live_result_count(), 0);
+
+    // Combining frees every input as well as what it returns
+    let results = [
+        ExternResult::ok_null(),
+        ExternResult::err(ErrorCode::TimeoutError, "Timed out"),
+        ExternResult::err(ErrorCode::IoError, "Disk full"),
+    ];
+    let combined = unsafe { combine_results(&results) };
+    assert_eq!(ffi_live_result_count(), expected.min(1));
+    drop(unsafe { OwnedExternResult::from_raw(combined) });
+
+    assert_eq!(ffi_live_result_count(), 0);
+}
@@ -0,0 +1,94 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Checking that memory is wiped before it is freed needs a global allocator
+// hook, so this lives in its own test binary instead of replacing the allocator
+// for every unit test.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use ffi_toolkit::define_zeroizing_destructor;
+use ffi_toolkit::memory::{destroy_raw_uuid, secure_destroy_c_char};
+use ffi_toolkit::string::string_to_c_char;
+
+/// Passes through to the system allocator, recording whether the block at
+/// the address in `WATCHED` held only zeros when it was freed.
+struct ZeroCheckingAlloc;
+
+std::thread_local! {
+    static WATCHED: Cell<(usize, Option<bool>)> = const { Cell::new((0, None)) };
+}
+
+unsafe impl GlobalAlloc for ZeroCheckingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = WATCHED.try_with(|watched| {
+            if watched.get().0 == ptr as usize {
+                let bytes = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+                watched.set((0, Some(bytes.iter().all(|&b| b == 0))));
+            }
+        });
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ZeroCheckingAlloc = ZeroCheckingAlloc;
+
+/// Runs `free` on `ptr` and returns whether the block was zeroed when freed,
+/// or `None` if it was not freed at all.
+fn zeroed_on_free<T>(ptr: *mut T, free: extern "C" fn(*mut T)) -> Option<bool> {
+    WATCHED.with(|watched| watched.set((ptr as usize, None)));
+    free(ptr);
+    WATCHED.with(|watched| watched.replace((0, None)).1)
+}
+
+struct Secret {
+    key: [u8; 32],
+    drops: Rc<Cell<usize>>,
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // The value is still intact when its own `Drop` runs
+        assert_eq!(self.key, [0xa5; 32]);
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+define_zeroizing_destructor!(destroy_secret, Secret);
+
+#[test]
+fn test_zeroizing_destructor_wipes_before_free() {
+    let drops = Rc::new(Cell::new(0));
+    let secret = Box::into_raw(Box::new(Secret {
+        key: [0xa5; 32],
+        drops: drops.clone(),
+    }));
+
+    assert_eq!(zeroed_on_free(secret, destroy_secret), Some(true));
+    assert_eq!(drops.get(), 1);
+    destroy_secret(std::ptr::null_mut());
+}
+
+#[test]
+fn test_plain_destructor_does_not_wipe() {
+    // Checks that the allocator hook can tell the difference
+    let key = Box::into_raw(Box::new([0xa5u8; 16]));
+
+    assert_eq!(zeroed_on_free(key, destroy_raw_uuid), Some(false));
+}
+
+#[test]
+fn test_secure_destroy_c_char_wipes_before_free() {
+    let password = string_to_c_char("hunter2");
+
+    assert_eq!(zeroed_on_free(password, secure_destroy_c_char), Some(true));
+    secure_destroy_c_char(std::ptr::null_mut());
+}